@@ -1,7 +1,21 @@
-use crate::{db::DbPool, github_client::GithubClient};
+use crate::{
+    db::DbPool, frontend::Frontend, github_client::GithubClient, link_checker::LinkChecker,
+    page_metadata::PageMetadataClient,
+};
+use std::path::PathBuf;
 
 #[derive(Clone)]
 pub struct ApplicationContext {
     pub github_client: GithubClient,
+    pub link_checker: LinkChecker,
+    pub page_metadata_client: PageMetadataClient,
     pub db_pool: DbPool,
+    /// The public URL of the site, without a trailing slash. Used wherever we
+    /// hand out absolute links, such as in feeds.
+    pub site_url: String,
+    /// Where uploaded icons are kept.
+    pub icon_dir: PathBuf,
+    /// The React app's build, when we serve it ourselves rather than leave
+    /// it to nginx.
+    pub frontend: Option<Frontend>,
 }
@@ -29,7 +29,7 @@ impl Opts {
             SubCommand::Migrate(m) => m.migrate(&ctxt),
             SubCommand::Permission(p) => p.do_the_thing(&ctxt).await,
             SubCommand::Serve(s) => s.serve(&ctxt).await,
-            SubCommand::Snippet(s) => s.do_the_thing(&ctxt),
+            SubCommand::Snippet(s) => s.do_the_thing(&ctxt).await,
         }
     }
 }
@@ -1,6 +1,20 @@
-use crate::application_context::ApplicationContext;
+use crate::{
+    application_context::ApplicationContext,
+    frontend::{Frontend, FrontendSource},
+    link_checker::check_snippets,
+};
 use clap::Clap;
-use rocket::{config::Config as RocketConfig, figment::Figment, routes};
+use log::{error, info};
+use rocket::{
+    config::Config as RocketConfig,
+    figment::Figment,
+    routes,
+    tokio::{
+        self,
+        time::{interval_at, Instant},
+    },
+};
+use std::{env, process::exit, time::Duration};
 
 /// Start the iDevGames website
 #[derive(Clap, Debug)]
@@ -17,8 +31,41 @@ impl Serve {
             .merge(("port", port))
             .merge(("secret_key", secret));
 
+        // without a build to serve, the app is left to something like nginx
+        let frontend = match env::var("IDG_STATIC_DIR") {
+            Ok(static_dir) => Some(Frontend::new(FrontendSource::Dir(static_dir.into()))),
+            Err(_) => Frontend::embedded(),
+        };
+        let ctxt = match frontend.transpose() {
+            Ok(frontend) => ApplicationContext {
+                frontend,
+                ..ctxt.clone()
+            },
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(-1);
+            }
+        };
+
+        // checking links means knocking on the door of every site we've ever
+        // linked to, so it only happens when asked for.
+        if let Ok(hours) = env::var("IDG_LINK_CHECK_HOURS") {
+            let hours = hours
+                .parse::<u64>()
+                .expect("IDG_LINK_CHECK_HOURS should be a whole number of hours");
+            let auto_hide_after = env::var("IDG_LINK_CHECK_AUTO_HIDE").ok().map(|n| {
+                n.parse::<i32>()
+                    .expect("IDG_LINK_CHECK_AUTO_HIDE should be a whole number of checks")
+            });
+            spawn_link_checks(
+                ctxt.clone(),
+                Duration::from_secs(hours * 60 * 60),
+                auto_hide_after,
+            );
+        }
+
         let _ = rocket::custom(config)
-            .manage(ctxt.clone())
+            .manage(ctxt)
             .mount(
                 "/api",
                 routes![
@@ -30,19 +77,135 @@ impl Serve {
                     crate::controllers::auth::github_callback,
                     // DELETE   /api/session
                     crate::controllers::auth::delete,
-                    // GET      /api/snippets
+                    // GET      /api/snippets?taxonomy=string&cursor=string&limit=int&sort=string
                     crate::controllers::snippets::get_snippets,
                     // POST     /api/snippets
                     crate::controllers::snippets::create_snippet,
+                    // POST     /api/snippets/preview
+                    crate::controllers::snippets::preview_snippet,
+                    // GET      /api/snippets/feed.atom
+                    crate::controllers::feeds::all_snippets_atom,
+                    // GET      /api/snippets/feed.rss
+                    crate::controllers::feeds::all_snippets_rss,
+                    // GET      /api/snippets/<taxonomy>/feed.atom
+                    crate::controllers::feeds::taxonomy_snippets_atom,
+                    // GET      /api/snippets/<taxonomy>/feed.rss
+                    crate::controllers::feeds::taxonomy_snippets_rss,
+                    // GET      /api/snippets/search?q=string
+                    crate::controllers::snippets::search_snippets,
+                    // GET      /api/snippets/scheduled
+                    crate::controllers::snippets::get_scheduled_snippets,
+                    // GET      /api/snippets/trash
+                    crate::controllers::snippets::get_trash,
+                    // POST     /api/snippets/trash/<snippet_id>/restore
+                    crate::controllers::snippets::restore_snippet,
                     // GET      /api/snippets/<snippet_id>
                     crate::controllers::snippets::get_snippet,
                     // PUT      /api/snippets/<snippet_id>
                     crate::controllers::snippets::update_snippet,
                     // DELETE   /api/snippets/<snippet_id>
                     crate::controllers::snippets::delete_snippet,
+                    // PUT      /api/snippets/<snippet_id>/vote
+                    crate::controllers::snippets::vote_snippet,
+                    // DELETE   /api/snippets/<snippet_id>/vote
+                    crate::controllers::snippets::unvote_snippet,
+                    // PUT      /api/snippets/<snippet_id>/bookmark
+                    crate::controllers::bookmarks::bookmark_snippet,
+                    // DELETE   /api/snippets/<snippet_id>/bookmark
+                    crate::controllers::bookmarks::unbookmark_snippet,
+                    // GET      /api/snippets/<snippet_id>/comments
+                    crate::controllers::comments::get_comments,
+                    // POST     /api/snippets/<snippet_id>/comments
+                    crate::controllers::comments::create_comment,
+                    // PUT      /api/snippets/<snippet_id>/comments/<comment_id>
+                    crate::controllers::comments::update_comment,
+                    // DELETE   /api/snippets/<snippet_id>/comments/<comment_id>
+                    crate::controllers::comments::delete_comment,
+                    // GET      /api/snippets/<snippet_id>/revisions
+                    crate::controllers::snippet_revisions::get_snippet_revisions,
+                    // GET      /api/snippets/<snippet_id>/revisions/diff?from=int&to=int
+                    crate::controllers::snippet_revisions::diff_snippet_revisions,
+                    // POST     /api/snippets/<snippet_id>/revisions/<revision_id>/restore
+                    crate::controllers::snippet_revisions::restore_snippet_revision,
+                    // GET      /api/stats/clicks?since=date&until=date
+                    crate::controllers::clicks::get_click_stats,
+                    // GET      /api/icons
+                    crate::controllers::icons::get_icons,
+                    // POST     /api/icons?name=string&filename=string
+                    crate::controllers::icons::upload_icon,
+                    // GET      /api/icon-rules
+                    crate::controllers::icons::get_icon_rules,
+                    // POST     /api/icon-rules
+                    crate::controllers::icons::create_icon_rule,
+                    // DELETE   /api/icon-rules/<id>
+                    crate::controllers::icons::delete_icon_rule,
+                    // GET      /api/link-checks?dead_only=bool
+                    crate::controllers::link_checks::get_link_checks,
+                    // POST     /api/submissions
+                    crate::controllers::submissions::create_submission,
+                    // GET      /api/submissions?status=pending|approved|rejected
+                    crate::controllers::submissions::get_submissions,
+                    // GET      /api/submissions/mine
+                    crate::controllers::submissions::get_my_submissions,
+                    // POST     /api/submissions/<submission_id>/approve
+                    crate::controllers::submissions::approve_submission,
+                    // POST     /api/submissions/<submission_id>/reject
+                    crate::controllers::submissions::reject_submission,
+                    // GET      /api/taxonomies
+                    crate::controllers::taxonomies::get_taxonomies,
+                    // POST     /api/taxonomies
+                    crate::controllers::taxonomies::create_taxonomy,
+                    // GET      /api/taxonomies/<slug>
+                    crate::controllers::taxonomies::get_taxonomy,
+                    // PUT      /api/taxonomies/<slug>
+                    crate::controllers::taxonomies::update_taxonomy,
+                    // DELETE   /api/taxonomies/<slug>
+                    crate::controllers::taxonomies::delete_taxonomy,
+                    // GET      /api/users/me/bookmarks?cursor=string&limit=int
+                    crate::controllers::bookmarks::get_my_bookmarks,
+                ],
+            )
+            .mount(
+                "/",
+                routes![
+                    // GET      /
+                    crate::controllers::pages::get_home_page,
+                    // GET      /snippets/<taxonomy>/page/<page>
+                    crate::controllers::pages::get_taxonomy_page,
+                    // GET      /snippets/<taxonomy>/<snippet_id>
+                    crate::controllers::pages::get_snippet_page,
+                    // GET      /go/<snippet_id>
+                    crate::controllers::clicks::follow_snippet,
+                    // GET      /icons/<filename>
+                    crate::controllers::icons::get_icon_file,
+                    // GET      /robots.txt
+                    crate::controllers::sitemaps::get_robots_txt,
+                    // GET      /sitemap.xml
+                    crate::controllers::sitemaps::get_sitemap,
+                    // GET      /sitemaps/<page>.xml
+                    crate::controllers::sitemaps::get_sitemap_page,
+                    // GET      /<path..>, when serving the app's build
+                    crate::controllers::frontend::get_frontend_file,
                 ],
             )
             .launch()
             .await;
     }
 }
+
+/// Checks every snippet's link once per period, starting one period from now.
+fn spawn_link_checks(ctxt: ApplicationContext, period: Duration, auto_hide_after: Option<i32>) {
+    tokio::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            match check_snippets(&ctxt, auto_hide_after).await {
+                Ok(summary) => info!(
+                    "Checked {} links, {} dead, {} snippets hidden",
+                    summary.checked, summary.dead, summary.hidden
+                ),
+                Err(e) => error!("Failed to check links with error {}", e),
+            }
+        }
+    });
+}
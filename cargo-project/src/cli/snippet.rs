@@ -1,17 +1,43 @@
-use crate::application_context::ApplicationContext;
+use crate::{
+    application_context::ApplicationContext,
+    discord_import::{self, DiscordExport},
+    link_checker::check_snippets,
+    models::{
+        IconRule, LinkCheck, SnippetFilter, SnippetRevision, SnippetSort, Taxonomy, Visibility,
+    },
+    snippet_transfer::{self, TransferFormat},
+};
+use chrono::{Duration, Utc};
 use clap::Clap;
+use std::{fs::File, io, process::exit};
 
 /// Manages snippets.
 #[derive(Debug, Clap)]
 pub enum Snippet {
+    ApplyIconRules(ApplyIconRules),
+    CheckLinks(CheckLinks),
     Delete(Delete),
+    Export(Export),
+    FindDuplicates(FindDuplicates),
+    Import(Import),
+    ImportDiscord(ImportDiscord),
+    Purge(Purge),
+    Revert(Revert),
     SetTaxonomy(SetTaxonomy),
 }
 
 impl Snippet {
-    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+    pub async fn do_the_thing(&self, ctxt: &ApplicationContext) {
         match self {
+            Snippet::ApplyIconRules(air) => air.do_the_thing(ctxt),
+            Snippet::CheckLinks(cl) => cl.do_the_thing(ctxt).await,
             Snippet::Delete(d) => d.make_it_go_away(ctxt),
+            Snippet::Export(e) => e.do_the_thing(ctxt),
+            Snippet::FindDuplicates(fd) => fd.do_the_thing(ctxt),
+            Snippet::Import(i) => i.do_the_thing(ctxt),
+            Snippet::ImportDiscord(id) => id.do_the_thing(ctxt),
+            Snippet::Purge(p) => p.make_it_go_away_forever(ctxt),
+            Snippet::Revert(r) => r.do_the_thing(ctxt),
             Snippet::SetTaxonomy(st) => st.do_the_thing(ctxt),
         }
     }
@@ -22,20 +48,384 @@ pub struct SetTaxonomy {
     /// The id of the snippet.
     id: i32,
 
-    /// The taxonomy to set.
+    /// The taxonomy to set, by its slug. It must already exist.
     taxonomy: String,
 }
 
 impl SetTaxonomy {
     pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
         let conn = ctxt.db_pool.read().get().unwrap();
+
+        if Taxonomy::find_by_slug(&conn, &self.taxonomy)
+            .unwrap()
+            .is_none()
+        {
+            eprintln!("No such taxonomy {} exists!", &self.taxonomy);
+            exit(-1);
+        }
+
         let mut snippet = crate::models::snippets::Snippet::find_by_id(&conn, self.id).unwrap();
         snippet.taxonomy = self.taxonomy.clone();
-        snippet.update(&conn).unwrap();
+        snippet.update(&conn, None).unwrap();
         println!("Snippet updated!");
     }
 }
 
+/// Puts a snippet back the way it was as of an earlier revision.
+#[derive(Debug, Clap)]
+pub struct Revert {
+    /// The id of the snippet.
+    id: i32,
+
+    /// The id of the revision to go back to. Leave it off to undo the most
+    /// recent edit.
+    revision: Option<i32>,
+}
+
+impl Revert {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+        let mut snippet = crate::models::snippets::Snippet::find_by_id(&conn, self.id).unwrap();
+        let mut revisions = SnippetRevision::find_by_snippet_id(&conn, snippet.id).unwrap();
+
+        let revision = match self.revision {
+            Some(revision_id) => revisions.into_iter().find(|r| r.id == revision_id),
+            // the newest revision is the snippet as it is now
+            None if revisions.len() > 1 => Some(revisions.remove(1)),
+            None => None,
+        };
+        let revision = match revision {
+            Some(revision) => revision,
+            None => {
+                eprintln!("No such revision of snippet {} exists!", self.id);
+                exit(-1);
+            }
+        };
+
+        if Taxonomy::find_by_slug(&conn, &revision.taxonomy)
+            .unwrap()
+            .is_none()
+        {
+            eprintln!("No such taxonomy {} exists!", &revision.taxonomy);
+            exit(-1);
+        }
+
+        revision.restore(&conn, &mut snippet, None).unwrap();
+        println!("Snippet reverted to revision {}.", revision.id);
+    }
+}
+
+/// Gives every snippet the icon the icon rules pick for its link, for after
+/// the rules change. Snippets no rule matches are left alone.
+#[derive(Debug, Clap)]
+pub struct ApplyIconRules {
+    /// Say what would change, without saving anything.
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl ApplyIconRules {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+        let rules = IconRule::find_all(&conn).unwrap();
+        let filter = SnippetFilter {
+            visibility: Visibility::All,
+            ..SnippetFilter::default()
+        };
+        let snippets = crate::models::snippets::Snippet::find_filtered(
+            &conn,
+            &filter,
+            SnippetSort::CreatedAt,
+            0,
+            i64::MAX,
+        )
+        .unwrap();
+
+        let mut changed = 0;
+        for mut snippet in snippets {
+            let icon = match IconRule::pick(&rules, &snippet.href) {
+                Some(icon) if icon != snippet.icon => icon.to_owned(),
+                _ => continue,
+            };
+
+            println!(
+                "{}\t{}\t{} -> {}",
+                snippet.id, snippet.href, snippet.icon, icon
+            );
+            if !self.dry_run {
+                snippet.icon = icon;
+                snippet.update(&conn, None).unwrap();
+            }
+            changed += 1;
+        }
+
+        if self.dry_run {
+            println!("Would change the icons of {} snippets.", changed);
+        } else {
+            println!("Changed the icons of {} snippets.", changed);
+        }
+    }
+}
+
+/// Checks that every snippet's link still goes somewhere, and lists the ones
+/// which don't.
+#[derive(Debug, Clap)]
+pub struct CheckLinks {
+    /// Hide snippets whose links have been dead for this many checks in a
+    /// row, counting this one.
+    #[clap(long)]
+    auto_hide_after: Option<i32>,
+}
+
+impl CheckLinks {
+    pub async fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let summary = check_snippets(ctxt, self.auto_hide_after).await.unwrap();
+
+        let conn = ctxt.db_pool.read().get().unwrap();
+        for (snippet, link_check) in LinkCheck::find_report(&conn, true).unwrap() {
+            let outcome = match (link_check.status_code, &link_check.error) {
+                (Some(code), _) => code.to_string(),
+                (None, Some(error)) => error.clone(),
+                (None, None) => "no answer".to_owned(),
+            };
+            println!(
+                "{}\t{}\t{}\t{} in a row",
+                snippet.id, snippet.href, outcome, link_check.consecutive_failures
+            );
+        }
+
+        println!(
+            "Checked {} links, {} dead, {} snippets hidden.",
+            summary.checked, summary.dead, summary.hidden
+        );
+    }
+}
+
+/// Lists snippets which link to the same page, going by canonical href.
+#[derive(Debug, Clap)]
+pub struct FindDuplicates {
+    /// Move every duplicate but the oldest of each to the trash.
+    #[clap(long)]
+    trash: bool,
+}
+
+impl FindDuplicates {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+
+        // snippets from before canonical hrefs, or from before the rules
+        // last changed, need catching up first
+        let refreshed = crate::models::snippets::Snippet::refresh_canonical_hrefs(&conn).unwrap();
+        if refreshed > 0 {
+            println!("Refreshed {} canonical hrefs.", refreshed);
+        }
+
+        let groups = crate::models::snippets::Snippet::find_duplicates(&conn).unwrap();
+        for group in &groups {
+            println!("{}", group[0].canonical_href);
+            for (i, snippet) in group.iter().enumerate() {
+                println!("\t{}\t{}\t{}", snippet.id, snippet.href, snippet.title);
+                if self.trash && i > 0 {
+                    snippet.delete(&conn).unwrap();
+                }
+            }
+        }
+
+        if self.trash {
+            println!(
+                "Moved {} duplicates to the trash.",
+                groups.iter().map(|group| group.len() - 1).sum::<usize>()
+            );
+        } else {
+            println!("Found {} links shared more than once.", groups.len());
+        }
+    }
+}
+
+/// Writes out every snippet not in the trash, oldest first, as JSON Lines or
+/// CSV with the same fields as the API.
+#[derive(Debug, Clap)]
+pub struct Export {
+    /// The file to write to. Leave it off to write to stdout.
+    path: Option<String>,
+
+    /// jsonl or csv. Leave it off to go by the file's extension.
+    #[clap(long)]
+    format: Option<TransferFormat>,
+
+    /// Only export the snippets in this taxonomy, by its slug.
+    #[clap(long)]
+    taxonomy: Option<String>,
+}
+
+impl Export {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+        let filter = SnippetFilter {
+            taxonomy: self.taxonomy.clone(),
+            visibility: Visibility::All,
+            ..SnippetFilter::default()
+        };
+        let mut snippets = crate::models::snippets::Snippet::find_filtered(
+            &conn,
+            &filter,
+            SnippetSort::CreatedAt,
+            0,
+            i64::MAX,
+        )
+        .unwrap();
+        // oldest first, so that importing them keeps them in the same order
+        snippets.reverse();
+
+        let r = match &self.path {
+            Some(path) => snippet_transfer::export(
+                &snippets,
+                self.format
+                    .unwrap_or_else(|| TransferFormat::from_path(path)),
+                File::create(path).unwrap(),
+            ),
+            None => snippet_transfer::export(
+                &snippets,
+                self.format.unwrap_or(TransferFormat::JsonLines),
+                io::stdout(),
+            ),
+        };
+        if let Err(e) = r {
+            eprintln!("{}", e);
+            exit(-1);
+        }
+
+        eprintln!("Exported {} snippets.", snippets.len());
+    }
+}
+
+/// Reads snippets from a JSON Lines or CSV file like the ones `export`
+/// writes. Snippets which link to a page that already has a snippet update
+/// that snippet rather than making another.
+#[derive(Debug, Clap)]
+pub struct Import {
+    /// The file to read from.
+    path: String,
+
+    /// jsonl or csv. Leave it off to go by the file's extension.
+    #[clap(long)]
+    format: Option<TransferFormat>,
+
+    /// Check the file over and say what would happen, without saving
+    /// anything.
+    #[clap(long)]
+    dry_run: bool,
+
+    /// The user to credit new snippets to, rather than the creatorId in the
+    /// file, which likely means someone else in another environment.
+    #[clap(long)]
+    creator_id: Option<i32>,
+}
+
+impl Import {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+        let format = self
+            .format
+            .unwrap_or_else(|| TransferFormat::from_path(&self.path));
+
+        let rows = match File::open(&self.path)
+            .map_err(Into::into)
+            .and_then(|file| snippet_transfer::read_records(format, file))
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(-1);
+            }
+        };
+
+        let summary = snippet_transfer::import(&conn, rows, self.creator_id, self.dry_run).unwrap();
+        for (line, error) in &summary.failed {
+            eprintln!("Line {}: {}", line, error);
+        }
+
+        println!(
+            "{} {} snippets, updated {}, {} failed.",
+            if self.dry_run {
+                "Would have created"
+            } else {
+                "Created"
+            },
+            summary.created,
+            summary.updated,
+            summary.failed.len()
+        );
+        if !summary.failed.is_empty() {
+            exit(-1);
+        }
+    }
+}
+
+/// Makes hidden draft snippets out of the links shared in a Discord channel,
+/// from the JSON that DiscordChatExporter writes. Links which already have a
+/// snippet, even one in the trash, are skipped.
+#[derive(Debug, Clap)]
+pub struct ImportDiscord {
+    /// The exported channel.
+    path: String,
+
+    /// The taxonomy to file the drafts under, by its slug.
+    #[clap(long, default_value = "links")]
+    taxonomy: String,
+
+    /// The user to credit as the drafts' creator, likely whoever is going to
+    /// review them.
+    #[clap(long)]
+    creator_id: i32,
+}
+
+impl ImportDiscord {
+    pub fn do_the_thing(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+
+        let taxonomy = match Taxonomy::find_by_slug(&conn, &self.taxonomy).unwrap() {
+            Some(taxonomy) => taxonomy,
+            None => {
+                eprintln!("No such taxonomy {} exists!", &self.taxonomy);
+                exit(-1);
+            }
+        };
+
+        let export = match File::open(&self.path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                serde_json::from_reader::<_, DiscordExport>(io::BufReader::new(file))
+                    .map_err(|e| e.to_string())
+            }) {
+            Ok(export) => export,
+            Err(e) => {
+                eprintln!("Couldn't read {} with error {}", &self.path, e);
+                exit(-1);
+            }
+        };
+
+        let summary = discord_import::import(
+            &conn,
+            &export,
+            &taxonomy.slug,
+            &taxonomy.default_icon,
+            self.creator_id,
+        )
+        .unwrap();
+        for snippet in &summary.created {
+            println!("{}\t{}\t{}", snippet.id, snippet.href, snippet.shared_by);
+        }
+
+        println!(
+            "Made {} hidden drafts, skipped {} links which already have snippets.",
+            summary.created.len(),
+            summary.skipped
+        );
+    }
+}
+
+/// Moves a snippet to the trash.
 #[derive(Debug, Clap)]
 pub struct Delete {
     id: i32,
@@ -46,6 +436,25 @@ impl Delete {
         let conn = ctxt.db_pool.read().get().unwrap();
         let snippet = crate::models::snippets::Snippet::find_by_id(&conn, self.id).unwrap();
         snippet.delete(&conn).unwrap();
-        println!("Snippet deleted.");
+        println!("Snippet moved to the trash.");
+    }
+}
+
+/// Permanently removes snippets which have been in the trash for a while.
+#[derive(Debug, Clap)]
+pub struct Purge {
+    /// Only purge snippets which went into the trash at least this many days
+    /// ago. Zero empties the trash entirely.
+    #[clap(long)]
+    older_than: i64,
+}
+
+impl Purge {
+    pub fn make_it_go_away_forever(&self, ctxt: &ApplicationContext) {
+        let conn = ctxt.db_pool.read().get().unwrap();
+        let cutoff = Utc::now().naive_utc() - Duration::days(self.older_than);
+        let purged =
+            crate::models::snippets::Snippet::purge_deleted_before(&conn, &cutoff).unwrap();
+        println!("Purged {} snippets.", purged);
     }
 }
@@ -0,0 +1,90 @@
+use super::{
+    snippets::{find_viewable_snippet, page_size, GetSnippetsOutput, ListPosition, SortParam},
+    HandlerError,
+};
+use crate::{
+    application_context::ApplicationContext,
+    helpers::maybe_user::MaybeUser,
+    models::{Bookmark, SnippetFilter, SnippetSort, Visibility},
+};
+use rocket::{delete, get, put, serde::json::Json, State};
+use serde::Serialize;
+
+/* #region BookmarkSnippet */
+
+/// Saves a snippet to the logged in user's bookmarks. Bookmarking it again
+/// changes nothing.
+#[put("/snippets/<snippet_id>/bookmark")]
+pub async fn bookmark_snippet(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<BookmarkSnippetOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+    find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    Bookmark::create(&conn, snippet_id, user_id)?;
+
+    Ok(Json(BookmarkSnippetOutput { bookmarked: true }))
+}
+
+/// Removes a snippet from the logged in user's bookmarks, if it was there.
+#[delete("/snippets/<snippet_id>/bookmark")]
+pub async fn unbookmark_snippet(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<BookmarkSnippetOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+
+    // a bookmark can always be taken away, even from a snippet since hidden
+    Bookmark::delete(&conn, snippet_id, user_id)?;
+
+    Ok(Json(BookmarkSnippetOutput { bookmarked: false }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookmarkSnippetOutput {
+    bookmarked: bool,
+}
+
+/* #endregion */
+/* #region GetMyBookmarks */
+
+/// The logged in user's bookmarks, in any taxonomy, paged the same way as
+/// `get_snippets`. Bookmarks of snippets which have since been hidden are
+/// left out for anyone but admins.
+#[get("/users/me/bookmarks?<cursor>&<page>&<limit>&<sort>")]
+pub async fn get_my_bookmarks(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    cursor: Option<&str>,
+    page: Option<i32>,
+    limit: Option<i64>,
+    sort: Option<SortParam>,
+) -> Result<Json<GetSnippetsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+
+    let filter = SnippetFilter {
+        visibility: if user.is_admin() {
+            Visibility::All
+        } else {
+            Visibility::Visible
+        },
+        bookmarked_by: Some(user_id),
+        ..SnippetFilter::default()
+    };
+    let sort = sort.map(SnippetSort::from).unwrap_or(SnippetSort::SharedOn);
+    let position = ListPosition::from_query(cursor, page, sort)?;
+
+    let mut snippets = GetSnippetsOutput::new(&conn, position, page_size(limit), &filter, sort)?;
+    snippets.load_for_user(&conn, &user)?;
+
+    Ok(Json(snippets))
+}
+
+/* #endregion */
@@ -0,0 +1,96 @@
+use super::{snippets::find_viewable_snippet, HandlerError};
+use crate::{
+    application_context::ApplicationContext,
+    helpers::{admin_only::AdminOnly, maybe_user::MaybeUser},
+    models::{SnippetClick, SnippetClickTotal, TaxonomyClickTotal},
+};
+use chrono::{Duration, NaiveDate, Utc};
+use log::error;
+use rocket::{get, response::Redirect, serde::json::Json, State};
+use serde::Serialize;
+
+/// How many days of clicks the stats cover when not told otherwise.
+const DEFAULT_STATS_DAYS: i64 = 30;
+
+/* #region FollowSnippet */
+
+/// Sends the browser on to a snippet's link, counting the click on the way.
+/// Only the day is recorded, so clicks can't be traced back to anyone.
+#[get("/go/<snippet_id>")]
+pub async fn follow_snippet(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Redirect, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippet = find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    // a click that couldn't be counted is no reason to keep anyone from
+    // where they were going
+    if let Err(e) = SnippetClick::record(&conn, snippet.id, Utc::now().naive_utc().date()) {
+        error!(
+            "Couldn't count a click of snippet {} with error {}",
+            snippet.id, e
+        );
+    }
+
+    Ok(Redirect::found(snippet.href))
+}
+
+/* #endregion */
+/* #region GetClickStats */
+
+/// Clicks per snippet and per taxonomy, from `since` to `until`, both
+/// inclusive and given as YYYY-MM-DD. Covers the last 30 days up to today
+/// when left out.
+#[get("/stats/clicks?<since>&<until>")]
+pub async fn get_click_stats(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    since: Option<&str>,
+    until: Option<&str>,
+) -> Result<Json<GetClickStatsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+
+    let until = match until {
+        Some(until) => parse_day(until)?,
+        None => Utc::now().naive_utc().date(),
+    };
+    let since = match since {
+        Some(since) => parse_day(since)?,
+        None => until - Duration::days(DEFAULT_STATS_DAYS - 1),
+    };
+    if since > until {
+        return Err(HandlerError::InvalidInput(
+            "since must not be after until".to_owned(),
+        ));
+    }
+
+    let snippets = SnippetClick::totals_by_snippet(&conn, since, until)?;
+    let taxonomies = SnippetClick::totals_by_taxonomy(&conn, since, until)?;
+
+    Ok(Json(GetClickStatsOutput {
+        since,
+        until,
+        total_clicks: taxonomies.iter().map(|t| t.clicks).sum(),
+        snippets,
+        taxonomies,
+    }))
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate, HandlerError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| HandlerError::InvalidInput(format!("Invalid date {}", value)))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetClickStatsOutput {
+    since: NaiveDate,
+    until: NaiveDate,
+    total_clicks: i64,
+    snippets: Vec<SnippetClickTotal>,
+    taxonomies: Vec<TaxonomyClickTotal>,
+}
+
+/* #endregion */
@@ -0,0 +1,250 @@
+use super::{snippets::find_viewable_snippet, HandlerError};
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    helpers::maybe_user::MaybeUser,
+    markdown,
+    models::{Comment, GithubUserRecord, ModelError},
+};
+use chrono::NaiveDateTime;
+use rocket::{delete, get, post, put, serde::json::Json, State};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Who wrote a comment, as GitHub knows them.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAuthor {
+    login: String,
+    avatar_url: String,
+    html_url: String,
+}
+
+/// A comment as the API presents it: its body rendered to sanitized HTML,
+/// its author, and the replies to it, oldest first. Deleted comments are only
+/// kept for their replies and have neither a body nor an author.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentView {
+    id: i32,
+    parent_id: Option<i32>,
+    author: Option<CommentAuthor>,
+    body: String,
+    body_html: String,
+    created_at: NaiveDateTime,
+    updated_at: NaiveDateTime,
+    deleted: bool,
+    replies: Vec<CommentView>,
+}
+
+impl CommentView {
+    fn new(comment: Comment, authors: &HashMap<i32, GithubUserRecord>) -> Self {
+        let author = authors
+            .get(&comment.author_id)
+            .filter(|_| !comment.is_deleted())
+            .map(|record| CommentAuthor {
+                login: record.login.clone(),
+                avatar_url: record.avatar_url.clone(),
+                html_url: record.html_url.clone(),
+            });
+
+        Self {
+            id: comment.id,
+            parent_id: comment.parent_id,
+            author,
+            body_html: markdown::render(&comment.body),
+            body: comment.body,
+            created_at: comment.created_at,
+            updated_at: comment.updated_at,
+            deleted: comment.deleted_at.is_some(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Looks up the author of a single comment.
+    fn load(conn: &DbConn, comment: Comment) -> Result<Self, ModelError> {
+        let authors = GithubUserRecord::find_by_user_ids(conn, &[comment.author_id])?;
+
+        Ok(Self::new(comment, &authors))
+    }
+
+    /// Arranges a snippet's comments into threads, looking up all of their
+    /// authors in one go. Returns the comments which aren't replies, with the
+    /// rest hung beneath them.
+    fn load_threads(conn: &DbConn, comments: Vec<Comment>) -> Result<Vec<Self>, ModelError> {
+        let author_ids = comments.iter().map(|c| c.author_id).collect::<Vec<_>>();
+        let authors = GithubUserRecord::find_by_user_ids(conn, &author_ids)?;
+
+        let mut replies = HashMap::<i32, Vec<Self>>::new();
+        let mut threads = Vec::new();
+        // going newest first means every reply is ready before its parent
+        for comment in comments.into_iter().rev() {
+            let mut view = Self::new(comment, &authors);
+            view.replies = replies.remove(&view.id).unwrap_or_default();
+            view.replies.reverse();
+
+            match view.parent_id {
+                Some(parent_id) => replies.entry(parent_id).or_default().push(view),
+                None => threads.push(view),
+            }
+        }
+        threads.reverse();
+
+        Ok(threads)
+    }
+}
+
+/// Finds a comment on the given snippet which hasn't been deleted.
+fn find_comment(conn: &DbConn, snippet_id: i32, comment_id: i32) -> Result<Comment, HandlerError> {
+    let comment = Comment::find_by_id(conn, comment_id)?;
+    if comment.snippet_id != snippet_id || comment.is_deleted() {
+        return Err(HandlerError::NotFound);
+    }
+
+    Ok(comment)
+}
+
+fn require_body(body: &str) -> Result<&str, HandlerError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(HandlerError::InvalidInput(
+            "A comment can't be empty".to_owned(),
+        ));
+    }
+
+    Ok(body)
+}
+
+/* #region GetComments */
+
+/// The discussion of a snippet, as threads, oldest first. Anyone who can see
+/// the snippet can read it.
+#[get("/snippets/<snippet_id>/comments")]
+pub async fn get_comments(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<GetCommentsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    let comments = Comment::find_by_snippet_id(&conn, snippet_id)?;
+
+    Ok(Json(GetCommentsOutput {
+        comments: CommentView::load_threads(&conn, comments)?,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetCommentsOutput {
+    comments: Vec<CommentView>,
+}
+
+/* #endregion */
+/* #region CreateComment */
+
+/// Comments on a snippet, or replies to a comment on it when given a
+/// `parentId`. Anyone who's logged in and can see the snippet can comment.
+#[post("/snippets/<snippet_id>/comments", data = "<input>")]
+pub async fn create_comment(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    input: Json<CreateCommentInput>,
+) -> Result<Json<CommentOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let author_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+    find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    let body = require_body(&input.body)?;
+    if let Some(parent_id) = input.parent_id {
+        find_comment(&conn, snippet_id, parent_id).map_err(|_| {
+            HandlerError::InvalidInput(format!("No such comment {} to reply to", parent_id))
+        })?;
+    }
+
+    let comment = Comment::create(&conn, snippet_id, input.parent_id, author_id, body)?;
+
+    Ok(Json(CommentOutput {
+        comment: CommentView::load(&conn, comment)?,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCommentInput {
+    body: String,
+    /// The comment this replies to, if any.
+    parent_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentOutput {
+    comment: CommentView,
+}
+
+/* #endregion */
+/* #region UpdateComment */
+
+/// Edits a comment. Only its author may.
+#[put("/snippets/<snippet_id>/comments/<comment_id>", data = "<input>")]
+pub async fn update_comment(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    comment_id: i32,
+    input: Json<UpdateCommentInput>,
+) -> Result<Json<CommentOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+    find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    let mut comment = find_comment(&conn, snippet_id, comment_id)?;
+    if comment.author_id != user_id {
+        return Err(HandlerError::Forbidden);
+    }
+    comment.edit(&conn, require_body(&input.body)?)?;
+
+    Ok(Json(CommentOutput {
+        comment: CommentView::load(&conn, comment)?,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCommentInput {
+    body: String,
+}
+
+/* #endregion */
+/* #region DeleteComment */
+
+/// Deletes a comment. Its author may, and so may admins, to keep the peace.
+/// Replies to it are left alone.
+#[delete("/snippets/<snippet_id>/comments/<comment_id>")]
+pub async fn delete_comment(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    comment_id: i32,
+) -> Result<Json<DeleteCommentOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(HandlerError::NotLoggedIn)?;
+    find_viewable_snippet(&conn, &user, snippet_id)?;
+
+    let mut comment = find_comment(&conn, snippet_id, comment_id)?;
+    if comment.author_id != user_id && !user.is_admin() {
+        return Err(HandlerError::Forbidden);
+    }
+    comment.delete(&conn)?;
+
+    Ok(Json(DeleteCommentOutput {}))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteCommentOutput {}
+
+/* #endregion */
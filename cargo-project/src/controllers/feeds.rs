@@ -0,0 +1,285 @@
+//! Atom and RSS feeds of snippets, one per taxonomy plus one for everything.
+//! Feeds only ever include visible snippets, regardless of who is asking,
+//! since feed readers don't carry our session cookie anyway.
+
+use super::HandlerError;
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    models::{Snippet, SnippetFilter, SnippetSort, Taxonomy},
+};
+use atom_syndication::{
+    EntryBuilder, FeedBuilder, FixedDateTime, LinkBuilder, PersonBuilder, Text,
+};
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use rocket::{get, http::ContentType, State};
+use rss::{ChannelBuilder, GuidBuilder, ItemBuilder};
+
+/// How many of the most recently shared snippets go into a feed.
+const FEED_SIZE: i64 = 20;
+
+/* #region Routes */
+
+#[get("/snippets/feed.atom")]
+pub async fn all_snippets_atom(
+    ctxt: &State<ApplicationContext>,
+) -> Result<(ContentType, String), super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippets = Snippet::find_all(&conn, true, 0, FEED_SIZE)?;
+    let feed = SnippetFeed::new(&ctxt.site_url, None, &snippets);
+
+    Ok((atom_content_type(), feed.to_atom()))
+}
+
+#[get("/snippets/feed.rss")]
+pub async fn all_snippets_rss(
+    ctxt: &State<ApplicationContext>,
+) -> Result<(ContentType, String), super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippets = Snippet::find_all(&conn, true, 0, FEED_SIZE)?;
+    let feed = SnippetFeed::new(&ctxt.site_url, None, &snippets);
+
+    Ok((rss_content_type(), feed.to_rss()))
+}
+
+#[get("/snippets/<taxonomy>/feed.atom")]
+pub async fn taxonomy_snippets_atom(
+    ctxt: &State<ApplicationContext>,
+    taxonomy: &str,
+) -> Result<(ContentType, String), super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    require_visible_taxonomy(&conn, taxonomy)?;
+    let snippets = Snippet::find_filtered(
+        &conn,
+        &SnippetFilter::in_taxonomy(taxonomy),
+        SnippetSort::SharedOn,
+        0,
+        FEED_SIZE,
+    )?;
+    let feed = SnippetFeed::new(&ctxt.site_url, Some(taxonomy), &snippets);
+
+    Ok((atom_content_type(), feed.to_atom()))
+}
+
+#[get("/snippets/<taxonomy>/feed.rss")]
+pub async fn taxonomy_snippets_rss(
+    ctxt: &State<ApplicationContext>,
+    taxonomy: &str,
+) -> Result<(ContentType, String), super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    require_visible_taxonomy(&conn, taxonomy)?;
+    let snippets = Snippet::find_filtered(
+        &conn,
+        &SnippetFilter::in_taxonomy(taxonomy),
+        SnippetSort::SharedOn,
+        0,
+        FEED_SIZE,
+    )?;
+    let feed = SnippetFeed::new(&ctxt.site_url, Some(taxonomy), &snippets);
+
+    Ok((rss_content_type(), feed.to_rss()))
+}
+
+/// Feeds are public, so hidden taxonomies don't have one.
+fn require_visible_taxonomy(conn: &DbConn, slug: &str) -> Result<(), HandlerError> {
+    match Taxonomy::find_by_slug(conn, slug)? {
+        Some(taxonomy) if !taxonomy.hidden => Ok(()),
+        _ => Err(HandlerError::NotFound),
+    }
+}
+
+fn atom_content_type() -> ContentType {
+    ContentType::new("application", "atom+xml")
+}
+
+fn rss_content_type() -> ContentType {
+    ContentType::new("application", "rss+xml")
+}
+
+/* #endregion */
+
+/// Everything needed to render a list of snippets as either flavor of feed.
+struct SnippetFeed<'a> {
+    site_url: &'a str,
+    taxonomy: Option<&'a str>,
+    snippets: &'a [Snippet],
+}
+
+impl<'a> SnippetFeed<'a> {
+    fn new(site_url: &'a str, taxonomy: Option<&'a str>, snippets: &'a [Snippet]) -> Self {
+        Self {
+            site_url: site_url.trim_end_matches('/'),
+            taxonomy,
+            snippets,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.taxonomy {
+            Some(taxonomy) => format!("iDevGames: {}", taxonomy),
+            None => "iDevGames".to_owned(),
+        }
+    }
+
+    /// The URL of this feed, sans extension.
+    fn feed_url(&self) -> String {
+        match self.taxonomy {
+            Some(taxonomy) => format!("{}/api/snippets/{}/feed", self.site_url, taxonomy),
+            None => format!("{}/api/snippets/feed", self.site_url),
+        }
+    }
+
+    /// Where a person would go to see this feed on the site.
+    fn page_url(&self) -> String {
+        match self.taxonomy {
+            Some(taxonomy) => format!("{}/snippets/{}/page/0", self.site_url, taxonomy),
+            None => format!("{}/", self.site_url),
+        }
+    }
+
+    /// The feed is as fresh as its most recently updated snippet. When there
+    /// are no snippets use the epoch rather than now, so that an empty feed
+    /// doesn't look like it changes on every fetch.
+    fn updated(&self) -> FixedDateTime {
+        self.snippets
+            .iter()
+            .map(|snippet| snippet.updated_at)
+            .max()
+            .map(|updated| to_fixed(&updated))
+            .unwrap_or_else(|| Utc.timestamp_opt(0, 0).unwrap().into())
+    }
+
+    fn to_atom(&self) -> String {
+        let entries = self
+            .snippets
+            .iter()
+            .map(|snippet| {
+                EntryBuilder::default()
+                    .id(entry_id(snippet))
+                    .title(snippet.title.clone())
+                    .updated(to_fixed(&snippet.updated_at))
+                    .published(Some(to_fixed(&snippet.shared_on)))
+                    .author(
+                        PersonBuilder::default()
+                            .name(snippet.shared_by.clone())
+                            .build(),
+                    )
+                    .link(
+                        LinkBuilder::default()
+                            .href(snippet.href.clone())
+                            .rel("alternate")
+                            .build(),
+                    )
+                    .link(
+                        LinkBuilder::default()
+                            .href(self.snippet_url(snippet))
+                            .rel("related")
+                            .build(),
+                    )
+                    .summary(Some(Text::plain(snippet.summary.clone())))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        FeedBuilder::default()
+            .id(format!("{}.atom", self.feed_url()))
+            .title(self.title())
+            .updated(self.updated())
+            .link(
+                LinkBuilder::default()
+                    .href(format!("{}.atom", self.feed_url()))
+                    .rel("self")
+                    .build(),
+            )
+            .link(
+                LinkBuilder::default()
+                    .href(self.page_url())
+                    .rel("alternate")
+                    .build(),
+            )
+            .entries(entries)
+            .build()
+            .to_string()
+    }
+
+    fn to_rss(&self) -> String {
+        let items = self
+            .snippets
+            .iter()
+            .map(|snippet| {
+                ItemBuilder::default()
+                    .guid(Some(
+                        GuidBuilder::default()
+                            .value(entry_id(snippet))
+                            .permalink(false)
+                            .build(),
+                    ))
+                    .title(Some(snippet.title.clone()))
+                    .link(Some(snippet.href.clone()))
+                    .comments(Some(self.snippet_url(snippet)))
+                    .description(Some(snippet.summary.clone()))
+                    .author(Some(snippet.shared_by.clone()))
+                    .pub_date(Some(to_fixed(&snippet.shared_on).to_rfc2822()))
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        ChannelBuilder::default()
+            .title(self.title())
+            .link(self.page_url())
+            .description("Links shared by the iDevGames community")
+            .last_build_date(Some(self.updated().to_rfc2822()))
+            .items(items)
+            .build()
+            .to_string()
+    }
+
+    fn snippet_url(&self, snippet: &Snippet) -> String {
+        format!(
+            "{}/snippets/{}/{}",
+            self.site_url, snippet.taxonomy, snippet.id
+        )
+    }
+}
+
+/// A stable id for a snippet's entry in any feed. This deliberately does not
+/// include the taxonomy or site URL, both of which can change, so that feed
+/// readers don't see a moved snippet as a brand new one.
+fn entry_id(snippet: &Snippet) -> String {
+    format!("tag:idevgames.com,2021:snippets/{}", snippet.id)
+}
+
+/// Timestamps are stored as naive UTC.
+fn to_fixed(dt: &NaiveDateTime) -> FixedDateTime {
+    Utc.from_utc_datetime(dt).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feeds() {
+        let snippet = Snippet {
+            id: 42,
+            taxonomy: "links".to_owned(),
+            title: "Fish & Chips".to_owned(),
+            updated_at: Utc.timestamp_opt(1_600_000_000, 0).unwrap().naive_utc(),
+            ..Snippet::default()
+        };
+        let snippets = vec![snippet];
+        let feed = SnippetFeed::new("https://www.idevgames.com/", Some("links"), &snippets);
+
+        let atom = feed.to_atom();
+        assert!(atom.contains("<id>tag:idevgames.com,2021:snippets/42</id>"));
+        assert!(atom.contains("<title>Fish &amp; Chips</title>"));
+        assert!(atom.contains("<updated>2020-09-13T12:26:40+00:00</updated>"));
+        assert!(atom.contains("https://www.idevgames.com/api/snippets/links/feed.atom"));
+
+        let rss = feed.to_rss();
+        assert!(
+            rss.contains("<guid isPermaLink=\"false\">tag:idevgames.com,2021:snippets/42</guid>")
+        );
+        assert!(rss.contains("<lastBuildDate>Sun, 13 Sep 2020 12:26:40 +0000</lastBuildDate>"));
+    }
+}
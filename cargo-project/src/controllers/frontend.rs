@@ -0,0 +1,43 @@
+use super::HandlerError;
+use crate::{
+    application_context::ApplicationContext, frontend::StaticFile,
+    helpers::accept_encoding::AcceptEncoding,
+};
+use rocket::{get, State};
+use std::path::PathBuf;
+
+/// Anything no other route wants is looked for in the app's build. Paths
+/// which aren't files are the app's own routes, so they get its index.html
+/// and the app takes it from there. Ranked after every other route, so that
+/// it never gets in the way of the API, `/go/`, or `/icons/`.
+#[get("/<path..>", rank = 20)]
+pub async fn get_frontend_file(
+    ctxt: &State<ApplicationContext>,
+    encodings: AcceptEncoding,
+    path: PathBuf,
+) -> Result<StaticFile, HandlerError> {
+    let frontend = ctxt.frontend.as_ref().ok_or(HandlerError::NotFound)?;
+
+    // a mistyped API route should say so, rather than answer with a page
+    if path.starts_with("api") {
+        return Err(HandlerError::NotFound);
+    }
+
+    let path = path
+        .iter()
+        .map(|segment| segment.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if let Some(file) = frontend.open(&path, &encodings).await {
+        return Ok(file);
+    }
+
+    // a missing script or image shouldn't get a page in its place
+    if path.rsplit('/').next().unwrap_or("").contains('.') {
+        return Err(HandlerError::NotFound);
+    }
+    frontend
+        .open("index.html", &encodings)
+        .await
+        .ok_or(HandlerError::NotFound)
+}
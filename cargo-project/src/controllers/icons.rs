@@ -0,0 +1,269 @@
+use super::HandlerError;
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    frontend::StaticFile,
+    helpers::{accept_encoding::AcceptEncoding, admin_only::AdminOnly},
+    models::{Icon, IconKind, IconRule},
+};
+use diesel::Connection;
+use rocket::{
+    data::{Data, ToByteUnit},
+    delete, get,
+    http::Header,
+    post,
+    serde::json::Json,
+    Responder, State,
+};
+use serde::{Deserialize, Serialize};
+
+/// The biggest icon that can be uploaded, in kibibytes. Icons are shown at
+/// the size of a line of text, so anything near this is already far too big.
+const MAX_ICON_KIB: u64 = 256;
+
+/// Icons are only ever looked at, so they're served with nothing they could
+/// do otherwise, in case an SVG gets something past the checks on uploads.
+const ICON_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// Makes sure that a snippet's icon is one we have, so that the site doesn't
+/// end up showing broken images.
+pub fn require_icon(conn: &DbConn, filename: &str) -> Result<(), HandlerError> {
+    if Icon::is_registered(conn, filename)? {
+        Ok(())
+    } else {
+        Err(HandlerError::InvalidInput(format!(
+            "No such icon {}",
+            filename
+        )))
+    }
+}
+
+/* #region GetIcons */
+
+/// Every icon a snippet can have, by name.
+#[get("/icons")]
+pub async fn get_icons(
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetIconsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let icons = Icon::find_all(&conn)?;
+
+    Ok(Json(GetIconsOutput { icons }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetIconsOutput {
+    icons: Vec<Icon>,
+}
+
+/* #endregion */
+/* #region UploadIcon */
+
+/// Adds an icon, sent as the body of the request. The filename decides what
+/// kind of image it has to be, which is one of PNG, SVG, or ICO, and the icon
+/// is then found at `/icons/<filename>`.
+#[post("/icons?<name>&<filename>", data = "<data>")]
+pub async fn upload_icon(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    name: &str,
+    filename: &str,
+    data: Data<'_>,
+) -> Result<Json<UploadIconOutput>, HandlerError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(HandlerError::InvalidInput("A name is required".to_owned()));
+    }
+    if !Icon::is_valid_filename(filename) {
+        return Err(HandlerError::InvalidInput(
+            "Filenames may only have lowercase letters, digits, dots, dashes, and underscores"
+                .to_owned(),
+        ));
+    }
+    let kind = IconKind::from_filename(filename).ok_or_else(|| {
+        HandlerError::InvalidInput("Icons must be .png, .svg, or .ico files".to_owned())
+    })?;
+
+    let bytes = data.open(MAX_ICON_KIB.kibibytes()).into_bytes().await?;
+    if !bytes.is_complete() {
+        return Err(HandlerError::InvalidInput(format!(
+            "Icons can be at most {} KiB",
+            MAX_ICON_KIB
+        )));
+    }
+    if !kind.matches(&bytes) {
+        return Err(HandlerError::InvalidInput(format!(
+            "The file isn't an image of type {}, or isn't one we can safely serve",
+            kind.content_type()
+        )));
+    }
+
+    let conn = ctxt.db_pool.read().get()?;
+    if Icon::find_by_filename(&conn, filename)?.is_some() {
+        return Err(HandlerError::Conflict(format!(
+            "There's already an icon called {}",
+            filename
+        )));
+    }
+    if Icon::find_by_name(&conn, name)?.is_some() {
+        return Err(HandlerError::Conflict(format!(
+            "There's already an icon named {}",
+            name
+        )));
+    }
+
+    // the row goes in first, so that losing a race with another upload of
+    // the same icon fails before its file is overwritten, and the file is
+    // written before committing, so that failing to write it leaves no row
+    // pointing at nothing
+    let icon = conn.transaction::<Icon, HandlerError, _>(|| {
+        let icon = Icon::create(&conn, name, filename, kind.content_type())?;
+        std::fs::create_dir_all(&ctxt.icon_dir)?;
+        std::fs::write(ctxt.icon_dir.join(filename), &*bytes)?;
+
+        Ok(icon)
+    })?;
+
+    Ok(Json(UploadIconOutput { icon }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadIconOutput {
+    icon: Icon,
+}
+
+/* #endregion */
+/* #region GetIconFile */
+
+/// Serves an uploaded icon, or when we're serving the app's build, one of
+/// those which ship with it. Otherwise those are served along with the rest
+/// of the build.
+#[get("/icons/<filename>")]
+pub async fn get_icon_file(
+    ctxt: &State<ApplicationContext>,
+    encodings: AcceptEncoding,
+    filename: &str,
+) -> Option<IconFile> {
+    if !Icon::is_valid_filename(filename) {
+        return None;
+    }
+
+    let file = match StaticFile::open(&ctxt.icon_dir.join(filename)).await {
+        Some(file) => Some(file),
+        None => match &ctxt.frontend {
+            Some(frontend) => {
+                frontend
+                    .open(&format!("icons/{}", filename), &encodings)
+                    .await
+            }
+            None => None,
+        },
+    };
+
+    file.map(|file| IconFile {
+        file,
+        csp: Header::new("Content-Security-Policy", ICON_CSP),
+        nosniff: Header::new("X-Content-Type-Options", "nosniff"),
+    })
+}
+
+#[derive(Responder)]
+pub struct IconFile {
+    file: StaticFile,
+    csp: Header<'static>,
+    nosniff: Header<'static>,
+}
+
+/* #endregion */
+/* #region GetIconRules */
+
+/// The rules for picking an icon from a link's site, by pattern.
+#[get("/icon-rules")]
+pub async fn get_icon_rules(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetIconRulesOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let icon_rules = IconRule::find_all(&conn)?;
+
+    Ok(Json(GetIconRulesOutput { icon_rules }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetIconRulesOutput {
+    icon_rules: Vec<IconRule>,
+}
+
+/* #endregion */
+/* #region CreateIconRule */
+
+/// Adds a rule, so that links to a site get its icon without anyone having
+/// to pick it.
+#[post("/icon-rules", data = "<input>")]
+pub async fn create_icon_rule(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    input: Json<CreateIconRuleInput>,
+) -> Result<Json<CreateIconRuleOutput>, HandlerError> {
+    let pattern = input.pattern.trim().to_lowercase();
+    if !IconRule::is_valid_pattern(&pattern) {
+        return Err(HandlerError::InvalidInput(
+            "Patterns are a host, such as example.com, or *. and a domain, such as *.example.com"
+                .to_owned(),
+        ));
+    }
+
+    let conn = ctxt.db_pool.read().get()?;
+    require_icon(&conn, &input.icon)?;
+    if IconRule::find_by_pattern(&conn, &pattern)?.is_some() {
+        return Err(HandlerError::Conflict(format!(
+            "There's already a rule for {}",
+            pattern
+        )));
+    }
+
+    let icon_rule = IconRule::create(&conn, &pattern, &input.icon)?;
+
+    Ok(Json(CreateIconRuleOutput { icon_rule }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateIconRuleInput {
+    /// A host, which also covers its `www.` subdomain, or `*.` and a domain,
+    /// which covers the domain and all of its subdomains.
+    pattern: String,
+    icon: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateIconRuleOutput {
+    icon_rule: IconRule,
+}
+
+/* #endregion */
+/* #region DeleteIconRule */
+
+/// Removes a rule. Snippets which already have its icon keep it.
+#[delete("/icon-rules/<id>")]
+pub async fn delete_icon_rule(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    id: i32,
+) -> Result<Json<DeleteIconRuleOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let icon_rule = IconRule::find_by_id(&conn, id)?.ok_or(HandlerError::NotFound)?;
+    icon_rule.delete(&conn)?;
+
+    Ok(Json(DeleteIconRuleOutput {}))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteIconRuleOutput {}
+
+/* #endregion */
@@ -0,0 +1,46 @@
+use super::HandlerError;
+use crate::{
+    application_context::ApplicationContext,
+    helpers::admin_only::AdminOnly,
+    models::{LinkCheck, Snippet},
+};
+use rocket::{get, serde::json::Json, State};
+use serde::Serialize;
+
+/// A snippet along with how the last check of its href went.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkReportEntry {
+    snippet: Snippet,
+    link_check: LinkCheck,
+}
+
+/* #region GetLinkChecks */
+
+/// How the last check of every snippet's href went, the longest-dead first.
+/// Pass `dead_only` to leave out the links which are fine.
+#[get("/link-checks?<dead_only>")]
+pub async fn get_link_checks(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    dead_only: Option<bool>,
+) -> Result<Json<GetLinkChecksOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let entries = LinkCheck::find_report(&conn, dead_only.unwrap_or(false))?
+        .into_iter()
+        .map(|(snippet, link_check)| LinkReportEntry {
+            snippet,
+            link_check,
+        })
+        .collect();
+
+    Ok(Json(GetLinkChecksOutput { entries }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetLinkChecksOutput {
+    entries: Vec<LinkReportEntry>,
+}
+
+/* #endregion */
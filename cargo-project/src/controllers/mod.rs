@@ -1,5 +1,17 @@
 pub mod auth;
+pub mod bookmarks;
+pub mod clicks;
+pub mod comments;
+pub mod feeds;
+pub mod frontend;
+pub mod icons;
+pub mod link_checks;
+pub mod pages;
+pub mod sitemaps;
+pub mod snippet_revisions;
 pub mod snippets;
+pub mod submissions;
+pub mod taxonomies;
 
 use crate::{github_client::GithubClientError, helpers::AuthFromRequestError};
 use rocket::{
@@ -23,6 +35,22 @@ pub enum HandlerError {
     #[error("The resource was not found")]
     NotFound,
 
+    #[error("No user is logged in")]
+    NotLoggedIn,
+
+    #[error("The user may not do that")]
+    Forbidden,
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// The link being shared already has a snippet, whose id this is.
+    #[error("Duplicate of snippet {0}")]
+    DuplicateSnippet(i32),
+
     #[error("Could not get a connection from the pool with error {0}")]
     PoolError(#[from] diesel::r2d2::PoolError),
 
@@ -40,30 +68,52 @@ pub enum HandlerError {
 
     #[error("Diesel Error {0}")]
     DieselError(#[from] diesel::result::Error),
+
+    #[error("IO Error {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Template Error {0}")]
+    TemplateError(#[from] askama::Error),
 }
 
 impl HandlerError {
     fn status_code(&self) -> Status {
         match self {
+            Self::DatabaseError(e) if e.is_not_found() => Status::NotFound,
             Self::DatabaseError(_) => Status::InternalServerError,
             Self::PoolError(_) => Status::InternalServerError,
             Self::HttpError(_) => Status::InternalServerError,
             Self::ParseError(_) => Status::BadRequest,
             Self::ParseIntError(_) => Status::BadRequest,
             Self::DieselError(_) => Status::InternalServerError,
+            Self::IoError(_) => Status::InternalServerError,
+            Self::TemplateError(_) => Status::InternalServerError,
             Self::NotFound => Status::NotFound,
+            Self::NotLoggedIn => Status::Unauthorized,
+            Self::Forbidden => Status::Forbidden,
+            Self::InvalidInput(_) => Status::BadRequest,
+            Self::Conflict(_) => Status::Conflict,
+            Self::DuplicateSnippet(_) => Status::Conflict,
         }
     }
 
     fn external_message(&self) -> &str {
         match self {
             Self::NotFound => "The resource was not found",
+            Self::NotLoggedIn => "You must be logged in to do that",
+            Self::Forbidden => "You are not allowed to do that",
+            Self::DatabaseError(e) if e.is_not_found() => "The resource was not found",
             Self::PoolError(_) => "Unable to connect to database",
             Self::DatabaseError(_) => "Unable to query database",
             Self::HttpError(_) => "Unable to contact a remote server",
             Self::ParseError(_) => "Unable to parse date",
             Self::ParseIntError(_) => "Unable to parse int",
             Self::DieselError(_) => "Unable to query database",
+            Self::IoError(_) => "Unable to read or write a file",
+            Self::TemplateError(_) => "Unable to render the page",
+            Self::InvalidInput(message) => message,
+            Self::Conflict(message) => message,
+            Self::DuplicateSnippet(_) => "This link has already been shared",
         }
     }
 }
@@ -71,10 +121,13 @@ impl HandlerError {
 impl<'r> Responder<'r, 'static> for HandlerError {
     fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
         let status_code = self.status_code();
-        let body = json!({
+        let mut body = json!({
             "message": self.external_message()
-        })
-        .to_string();
+        });
+        if let Self::DuplicateSnippet(snippet_id) = self {
+            body["existingSnippetId"] = json!(snippet_id);
+        }
+        let body = body.to_string();
 
         Response::build()
             .sized_body(body.len(), Cursor::new(body))
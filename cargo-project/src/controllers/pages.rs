@@ -0,0 +1,341 @@
+//! Server-rendered versions of the site's public pages, at the same paths
+//! the React app uses for them. Link previews, crawlers, and browsers without
+//! JavaScript never run the app, so without these they'd see an empty page
+//! with no idea what it's about.
+
+use super::{
+    snippets::{find_viewable_snippet, page_size},
+    taxonomies::find_visible_taxonomy,
+    HandlerError,
+};
+use crate::{
+    application_context::ApplicationContext,
+    helpers::maybe_user::MaybeUser,
+    markdown,
+    models::{Snippet, SnippetFilter, SnippetSort, Tag, Taxonomy},
+};
+use askama::Template;
+use chrono::{Datelike, Utc};
+use rocket::{get, http::ContentType, State};
+
+/// The longest a page's description gets in link previews, in characters.
+const MAX_DESCRIPTION_CHARS: usize = 200;
+
+const SITE_DESCRIPTION: &str = "Community and resources for hobby video game developers";
+
+/* #region Routes */
+
+/// The latest few snippets from each taxonomy, like the app's homepage.
+#[get("/")]
+pub async fn get_home_page(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+) -> Result<(ContentType, String), HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+
+    let mut sections = Vec::new();
+    for taxonomy in Taxonomy::find_all(&conn, !user.is_admin())? {
+        let filter = SnippetFilter::in_taxonomy(&taxonomy.slug);
+        let page_size = page_size(None);
+        let total_pages = (Snippet::count(&conn, &filter)? + page_size - 1) / page_size;
+        let snippets = Snippet::find_filtered(&conn, &filter, SnippetSort::SharedOn, 0, page_size)?;
+        sections.push(HomeSection {
+            snippets: snippets
+                .into_iter()
+                .map(|snippet| SnippetItem::new(&ctxt.site_url, snippet, Vec::new()))
+                .collect(),
+            taxonomy,
+            total_pages,
+        });
+    }
+
+    let page = HomePage {
+        meta: PageMeta {
+            title: "iDevGames".to_owned(),
+            description: SITE_DESCRIPTION.to_owned(),
+            url: format!("{}/", ctxt.site_url),
+            kind: "website",
+            image_url: image_url(&ctxt.site_url),
+            feed_url: Some(format!("{}/api/snippets/feed", ctxt.site_url)),
+        },
+        year: Utc::now().year(),
+        app_tags: app_tags(ctxt),
+        sections,
+    };
+
+    Ok((ContentType::HTML, page.render()?))
+}
+
+/// A page of a taxonomy's snippets, counting from zero like the app does.
+#[get("/snippets/<taxonomy>/page/<page>")]
+pub async fn get_taxonomy_page(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    taxonomy: &str,
+    page: i64,
+) -> Result<(ContentType, String), HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let taxonomy = find_visible_taxonomy(&conn, &user, taxonomy)?;
+
+    let filter = SnippetFilter::in_taxonomy(&taxonomy.slug);
+    let page_size = page_size(None);
+    let total_pages = (Snippet::count(&conn, &filter)? + page_size - 1) / page_size;
+    if page < 0 || (page > 0 && page >= total_pages) {
+        return Err(HandlerError::NotFound);
+    }
+    let snippets = Snippet::find_filtered(&conn, &filter, SnippetSort::SharedOn, page, page_size)?;
+
+    let title = if page == 0 {
+        format!("{} - iDevGames", taxonomy.display_name)
+    } else {
+        format!("{}, page {} - iDevGames", taxonomy.display_name, page + 1)
+    };
+    let description = if taxonomy.description.is_empty() {
+        SITE_DESCRIPTION.to_owned()
+    } else {
+        truncate(&taxonomy.description)
+    };
+    let page = TaxonomyPage {
+        meta: PageMeta {
+            title,
+            description,
+            url: format!("{}/snippets/{}/page/{}", ctxt.site_url, taxonomy.slug, page),
+            kind: "website",
+            image_url: image_url(&ctxt.site_url),
+            feed_url: Some(format!(
+                "{}/api/snippets/{}/feed",
+                ctxt.site_url, taxonomy.slug
+            )),
+        },
+        year: Utc::now().year(),
+        app_tags: app_tags(ctxt),
+        snippets: snippets
+            .into_iter()
+            .map(|snippet| SnippetItem::new(&ctxt.site_url, snippet, Vec::new()))
+            .collect(),
+        taxonomy,
+        page,
+        total_pages: total_pages.max(1),
+    };
+
+    Ok((ContentType::HTML, page.render()?))
+}
+
+/// A single snippet, with everything it says about the link.
+#[get("/snippets/<_taxonomy>/<snippet_id>")]
+pub async fn get_snippet_page(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    _taxonomy: &str,
+    snippet_id: i32,
+) -> Result<(ContentType, String), HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippet = find_viewable_snippet(&conn, &user, snippet_id)?;
+    let tags = Tag::find_names_by_snippet_id(&conn, snippet.id)?;
+
+    let description = markdown::render_plain(&snippet.summary);
+    let snippet = SnippetItem::new(&ctxt.site_url, snippet, tags);
+    let page = SnippetPage {
+        meta: PageMeta {
+            title: format!("{} - iDevGames", snippet.title),
+            description: if description.is_empty() {
+                SITE_DESCRIPTION.to_owned()
+            } else {
+                truncate(&description)
+            },
+            url: snippet.url.clone(),
+            kind: "article",
+            image_url: image_url(&ctxt.site_url),
+            feed_url: None,
+        },
+        year: Utc::now().year(),
+        app_tags: app_tags(ctxt),
+        snippet,
+    };
+
+    Ok((ContentType::HTML, page.render()?))
+}
+
+/* #endregion */
+
+/// What every page says about itself in its head, for link previews.
+struct PageMeta {
+    title: String,
+    description: String,
+    /// The page's own URL, which is also its canonical one.
+    url: String,
+    /// The Open Graph type of the page.
+    kind: &'static str,
+    image_url: String,
+    /// A feed of what's on the page, sans extension.
+    feed_url: Option<String>,
+}
+
+/// A snippet with everything formatted the way the pages show it.
+struct SnippetItem {
+    taxonomy: String,
+    icon: String,
+    title: String,
+    href: String,
+    shared_by: String,
+    /// The date it was shared on, as the app shows it.
+    shared_on: String,
+    summary: String,
+    summary_html: String,
+    description_html: String,
+    tags: Vec<String>,
+    /// Where the snippet's own page is.
+    url: String,
+}
+
+impl SnippetItem {
+    fn new(site_url: &str, snippet: Snippet, tags: Vec<String>) -> Self {
+        Self {
+            url: format!("{}/snippets/{}/{}", site_url, snippet.taxonomy, snippet.id),
+            shared_on: snippet.shared_on.format("%Y-%m-%d").to_string(),
+            summary: markdown::render_plain(&snippet.summary),
+            summary_html: markdown::render(&snippet.summary),
+            description_html: markdown::render(&snippet.description),
+            taxonomy: snippet.taxonomy,
+            icon: snippet.icon,
+            title: snippet.title,
+            href: snippet.href,
+            shared_by: snippet.shared_by,
+            tags,
+        }
+    }
+}
+
+struct HomeSection {
+    taxonomy: Taxonomy,
+    snippets: Vec<SnippetItem>,
+    total_pages: i64,
+}
+
+#[derive(Template)]
+#[template(path = "home.html")]
+struct HomePage {
+    meta: PageMeta,
+    year: i32,
+    app_tags: String,
+    sections: Vec<HomeSection>,
+}
+
+#[derive(Template)]
+#[template(path = "taxonomy.html")]
+struct TaxonomyPage {
+    meta: PageMeta,
+    year: i32,
+    app_tags: String,
+    taxonomy: Taxonomy,
+    snippets: Vec<SnippetItem>,
+    page: i64,
+    total_pages: i64,
+}
+
+#[derive(Template)]
+#[template(path = "snippet.html")]
+struct SnippetPage {
+    meta: PageMeta,
+    year: i32,
+    app_tags: String,
+    snippet: SnippetItem,
+}
+
+/// The tags which load the React app, when we're serving it, so that it
+/// takes over from the page for anyone who runs it.
+fn app_tags(ctxt: &ApplicationContext) -> String {
+    ctxt.frontend
+        .as_ref()
+        .map(|frontend| frontend.app_tags.clone())
+        .unwrap_or_default()
+}
+
+/// The picture link previews show alongside every page, which is the app's
+/// biggest icon.
+fn image_url(site_url: &str) -> String {
+    format!("{}/logo512.png", site_url)
+}
+
+/// Shortens text to fit in a link preview, on a word boundary where there
+/// is one.
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_DESCRIPTION_CHARS {
+        return text.to_owned();
+    }
+
+    let cut = text
+        .char_indices()
+        .nth(MAX_DESCRIPTION_CHARS - 1)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let shortened = &text[..cut];
+    let shortened = shortened
+        .rsplit_once(' ')
+        .map(|(start, _)| start)
+        .unwrap_or(shortened);
+
+    format!("{}…", shortened.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_snippet_page() {
+        let snippet = Snippet {
+            id: 42,
+            taxonomy: "links".to_owned(),
+            icon: "github.png".to_owned(),
+            title: "Fish & Chips".to_owned(),
+            href: "https://example.com/?a=1&b=2".to_owned(),
+            shared_by: "bob".to_owned(),
+            shared_on: NaiveDate::from_ymd_opt(2021, 2, 3)
+                .unwrap()
+                .and_hms_opt(4, 5, 6)
+                .unwrap(),
+            summary: "Some **bold** text <script>alert(1)</script>".to_owned(),
+            ..Snippet::default()
+        };
+        let snippet = SnippetItem::new("https://www.idevgames.com", snippet, vec!["rust".into()]);
+        let page = SnippetPage {
+            meta: PageMeta {
+                title: format!("{} - iDevGames", snippet.title),
+                description: "Some \"bold\" text".to_owned(),
+                url: snippet.url.clone(),
+                kind: "article",
+                image_url: image_url("https://www.idevgames.com"),
+                feed_url: None,
+            },
+            year: 2021,
+            app_tags: r#"<script src="/static/js/main.8e2a1c3f.chunk.js"></script>"#.to_owned(),
+            snippet,
+        };
+
+        let html = page.render().unwrap();
+        assert!(html.contains("<title>Fish &amp; Chips - iDevGames</title>"));
+        assert!(html
+            .contains(r#"<meta property="og:description" content="Some &quot;bold&quot; text">"#));
+        assert!(html.contains(
+            r#"<meta property="og:url" content="https://www.idevgames.com/snippets/links/42">"#
+        ));
+        assert!(html.contains(r#"<a href="https://example.com/?a=1&amp;b=2">"#));
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains(r#"<script src="/static/js/main.8e2a1c3f.chunk.js"></script>"#));
+        assert!(html.contains("shared on 2021-02-03"));
+        assert!(html.contains("Tagged rust"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!("short", truncate("short"));
+
+        let long = "word ".repeat(100);
+        let shortened = truncate(&long);
+        assert!(shortened.chars().count() <= MAX_DESCRIPTION_CHARS);
+        assert!(shortened.ends_with("word…"));
+    }
+}
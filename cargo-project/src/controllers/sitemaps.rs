@@ -0,0 +1,219 @@
+//! Sitemaps and robots.txt, so that search engines can find every snippet
+//! even though the site itself is rendered in the browser. Like feeds, these
+//! only ever list what anyone could see.
+
+use super::HandlerError;
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    models::{Snippet, SnippetFilter, SnippetSort, Taxonomy},
+};
+use chrono::NaiveDateTime;
+use rocket::{get, http::ContentType, State};
+
+/// How many snippets go into one sitemap. The protocol allows 50,000, but
+/// there's no need to load anywhere near that many snippets at once.
+const SITEMAP_SIZE: i64 = 10_000;
+
+/* #region Routes */
+
+/// A sitemap of everything, or when there are too many snippets for one
+/// sitemap, an index of the sitemaps under `/sitemaps/`.
+#[get("/sitemap.xml")]
+pub async fn get_sitemap(
+    ctxt: &State<ApplicationContext>,
+) -> Result<(ContentType, String), HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let page_count = count_pages(&conn)?;
+
+    let xml = if page_count > 1 {
+        to_sitemap_index(&ctxt.site_url, page_count)
+    } else {
+        to_urlset(&find_urls(&conn, &ctxt.site_url, 0)?)
+    };
+
+    Ok((ContentType::XML, xml))
+}
+
+/// One of the sitemaps in the index, as `<page>.xml`. The first also has
+/// the front page and the taxonomies in it.
+#[get("/sitemaps/<file>")]
+pub async fn get_sitemap_page(
+    ctxt: &State<ApplicationContext>,
+    file: &str,
+) -> Result<(ContentType, String), HandlerError> {
+    let page = file
+        .strip_suffix(".xml")
+        .and_then(|page| page.parse::<i64>().ok())
+        .ok_or(HandlerError::NotFound)?;
+
+    let conn = ctxt.db_pool.read().get()?;
+    if page < 0 || page >= count_pages(&conn)? {
+        return Err(HandlerError::NotFound);
+    }
+
+    Ok((
+        ContentType::XML,
+        to_urlset(&find_urls(&conn, &ctxt.site_url, page)?),
+    ))
+}
+
+/// Points crawlers at the sitemap, and keeps them away from the API and
+/// from following links through `/go/`, which would count as clicks.
+#[get("/robots.txt")]
+pub async fn get_robots_txt(ctxt: &State<ApplicationContext>) -> (ContentType, String) {
+    (ContentType::Plain, to_robots_txt(&ctxt.site_url))
+}
+
+/* #endregion */
+
+/// A page on the site, and when it last changed.
+#[derive(Debug)]
+struct SitemapUrl {
+    loc: String,
+    lastmod: Option<NaiveDateTime>,
+}
+
+/// How many sitemaps it takes to list every visible snippet. Always at
+/// least one, for the front page and taxonomies.
+fn count_pages(conn: &DbConn) -> Result<i64, HandlerError> {
+    let snippet_count = Snippet::count(conn, &SnippetFilter::default())?;
+
+    Ok(((snippet_count + SITEMAP_SIZE - 1) / SITEMAP_SIZE).max(1))
+}
+
+/// The pages in one sitemap, oldest snippets last.
+fn find_urls(conn: &DbConn, site_url: &str, page: i64) -> Result<Vec<SitemapUrl>, HandlerError> {
+    let mut urls = Vec::new();
+
+    // a list of snippets changes whenever the newest change to one of them
+    // does, so that's its lastmod
+    if page == 0 {
+        urls.push(SitemapUrl {
+            loc: format!("{}/", site_url),
+            lastmod: last_updated(conn, &SnippetFilter::default())?,
+        });
+        for taxonomy in Taxonomy::find_all(conn, true)? {
+            let lastmod = last_updated(conn, &SnippetFilter::in_taxonomy(&taxonomy.slug))?;
+            urls.push(SitemapUrl {
+                loc: format!("{}/snippets/{}/page/0", site_url, taxonomy.slug),
+                lastmod: lastmod.max(Some(taxonomy.updated_at)),
+            });
+        }
+    }
+
+    let snippets = Snippet::find_filtered(
+        conn,
+        &SnippetFilter::default(),
+        SnippetSort::CreatedAt,
+        page,
+        SITEMAP_SIZE,
+    )?;
+    urls.extend(snippets.into_iter().map(|snippet| SitemapUrl {
+        loc: format!("{}/snippets/{}/{}", site_url, snippet.taxonomy, snippet.id),
+        lastmod: Some(snippet.updated_at),
+    }));
+
+    Ok(urls)
+}
+
+fn last_updated(
+    conn: &DbConn,
+    filter: &SnippetFilter,
+) -> Result<Option<NaiveDateTime>, HandlerError> {
+    let newest = Snippet::find_filtered(conn, filter, SnippetSort::UpdatedAt, 0, 1)?;
+
+    Ok(newest.first().map(|snippet| snippet.updated_at))
+}
+
+fn to_urlset(urls: &[SitemapUrl]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for url in urls {
+        xml.push_str("<url><loc>");
+        xml.push_str(&escape(&url.loc));
+        xml.push_str("</loc>");
+        if let Some(lastmod) = &url.lastmod {
+            xml.push_str("<lastmod>");
+            xml.push_str(&to_w3c(lastmod));
+            xml.push_str("</lastmod>");
+        }
+        xml.push_str("</url>\n");
+    }
+    xml.push_str("</urlset>\n");
+
+    xml
+}
+
+fn to_sitemap_index(site_url: &str, page_count: i64) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for page in 0..page_count {
+        xml.push_str(&format!(
+            "<sitemap><loc>{}/sitemaps/{}.xml</loc></sitemap>\n",
+            escape(site_url),
+            page
+        ));
+    }
+    xml.push_str("</sitemapindex>\n");
+
+    xml
+}
+
+fn to_robots_txt(site_url: &str) -> String {
+    format!(
+        "User-agent: *\nDisallow: /api/\nDisallow: /go/\n\nSitemap: {}/sitemap.xml\n",
+        site_url
+    )
+}
+
+/// Timestamps are stored as naive UTC.
+fn to_w3c(dt: &NaiveDateTime) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_sitemaps() {
+        let urls = vec![
+            SitemapUrl {
+                loc: "https://www.idevgames.com/".to_owned(),
+                lastmod: None,
+            },
+            SitemapUrl {
+                loc: "https://www.idevgames.com/snippets/fish&chips/42".to_owned(),
+                lastmod: Some(Utc.timestamp_opt(1_600_000_000, 0).unwrap().naive_utc()),
+            },
+        ];
+        let urlset = to_urlset(&urls);
+        assert!(urlset.contains("<url><loc>https://www.idevgames.com/</loc></url>"));
+        assert!(urlset.contains(
+            "<url><loc>https://www.idevgames.com/snippets/fish&amp;chips/42</loc>\
+             <lastmod>2020-09-13T12:26:40Z</lastmod></url>"
+        ));
+
+        let index = to_sitemap_index("https://www.idevgames.com", 2);
+        assert!(index.contains("<loc>https://www.idevgames.com/sitemaps/0.xml</loc>"));
+        assert!(index.contains("<loc>https://www.idevgames.com/sitemaps/1.xml</loc>"));
+        assert!(!index.contains("/sitemaps/2.xml"));
+
+        assert!(to_robots_txt("https://www.idevgames.com")
+            .ends_with("Sitemap: https://www.idevgames.com/sitemap.xml\n"));
+    }
+}
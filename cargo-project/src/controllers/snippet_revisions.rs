@@ -0,0 +1,103 @@
+use super::{taxonomies::require_taxonomy, HandlerError};
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    helpers::admin_only::AdminOnly,
+    models::{snippet_revisions::FieldChange, Snippet, SnippetRevision},
+};
+use rocket::{get, post, serde::json::Json, State};
+use serde::Serialize;
+
+/// Finds a revision, making sure that it belongs to the snippet in the URL.
+fn find_revision(
+    conn: &DbConn,
+    snippet_id: i32,
+    revision_id: i32,
+) -> Result<SnippetRevision, HandlerError> {
+    match SnippetRevision::find_by_id(conn, revision_id)? {
+        Some(revision) if revision.snippet_id == snippet_id => Ok(revision),
+        _ => Err(HandlerError::NotFound),
+    }
+}
+
+/* #region GetSnippetRevisions */
+
+/// Every revision of a snippet, newest first.
+#[get("/snippets/<snippet_id>/revisions")]
+pub async fn get_snippet_revisions(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<GetSnippetRevisionsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippet = Snippet::find_by_id(&conn, snippet_id)?;
+    let revisions = SnippetRevision::find_by_snippet_id(&conn, snippet.id)?;
+
+    Ok(Json(GetSnippetRevisionsOutput { revisions }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSnippetRevisionsOutput {
+    revisions: Vec<SnippetRevision>,
+}
+
+/* #endregion */
+/* #region DiffSnippetRevisions */
+
+/// What changed going from one revision of a snippet to another. The two
+/// revisions can be given in either order, to see an edit or to see what it
+/// would take to undo it.
+#[get("/snippets/<snippet_id>/revisions/diff?<from>&<to>")]
+pub async fn diff_snippet_revisions(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    from: i32,
+    to: i32,
+) -> Result<Json<DiffSnippetRevisionsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let from = find_revision(&conn, snippet_id, from)?;
+    let to = find_revision(&conn, snippet_id, to)?;
+
+    Ok(Json(DiffSnippetRevisionsOutput {
+        changes: from.diff(&to),
+    }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSnippetRevisionsOutput {
+    changes: Vec<FieldChange>,
+}
+
+/* #endregion */
+/* #region RestoreSnippetRevision */
+
+/// Puts a snippet and its tags back the way they were as of some revision.
+/// The restoration is itself recorded as a new revision, so it can be undone
+/// in turn.
+#[post("/snippets/<snippet_id>/revisions/<revision_id>/restore")]
+pub async fn restore_snippet_revision(
+    user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    revision_id: i32,
+) -> Result<Json<RestoreSnippetRevisionOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let mut snippet = Snippet::find_by_id(&conn, snippet_id)?;
+    let revision = find_revision(&conn, snippet_id, revision_id)?;
+
+    // the taxonomy may have been deleted since this revision was made
+    require_taxonomy(&conn, &revision.taxonomy)?;
+
+    revision.restore(&conn, &mut snippet, Some(user.user.0.id))?;
+
+    Ok(Json(RestoreSnippetRevisionOutput {}))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSnippetRevisionOutput {}
+
+/* #endregion */
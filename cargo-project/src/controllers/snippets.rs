@@ -1,60 +1,436 @@
+use super::{
+    icons::require_icon,
+    taxonomies::{find_visible_taxonomy, require_taxonomy},
+};
 use crate::{
     application_context::ApplicationContext,
     db::DbConn,
+    favicons,
     helpers::{admin_only::AdminOnly, maybe_user::MaybeUser},
-    models::{ModelError, Snippet},
+    markdown,
+    models::{
+        Bookmark, IconRule, ModelError, Snippet, SnippetCursor, SnippetFilter, SnippetSearchHit,
+        SnippetSort, SnippetVote, Tag, TagMatch, Taxonomy, Visibility,
+    },
 };
-use chrono::{DateTime, FixedOffset};
-use rocket::{delete, get, post, put, serde::json::Json, State};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use diesel::Connection;
+use rocket::{delete, get, post, put, serde::json::Json, FromForm, FromFormField, State};
 use serde::{Deserialize, Serialize};
 
+/// A snippet as the API presents it to readers: all of the snippet's own
+/// fields, plus its Markdown rendered to sanitized HTML and its tags.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetView {
+    #[serde(flatten)]
+    snippet: Snippet,
+    summary_html: String,
+    description_html: String,
+    tags: Vec<String>,
+    /// Whether the logged in user has voted this up, absent for anyone else.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    voted: Option<bool>,
+    /// Whether the logged in user has bookmarked this, absent for anyone
+    /// else.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bookmarked: Option<bool>,
+}
+
+impl SnippetView {
+    pub fn new(snippet: Snippet, tags: Vec<String>) -> Self {
+        Self {
+            summary_html: markdown::render(&snippet.summary),
+            description_html: markdown::render(&snippet.description),
+            snippet,
+            tags,
+            voted: None,
+            bookmarked: None,
+        }
+    }
+
+    /// Looks up the tags for a single snippet.
+    pub fn load(conn: &DbConn, snippet: Snippet) -> Result<Self, ModelError> {
+        let tags = Tag::find_names_by_snippet_id(conn, snippet.id)?;
+
+        Ok(Self::new(snippet, tags))
+    }
+
+    /// Looks up the tags for a list of snippets all in one go.
+    pub fn load_all(conn: &DbConn, snippets: Vec<Snippet>) -> Result<Vec<Self>, ModelError> {
+        let ids = snippets
+            .iter()
+            .map(|snippet| snippet.id)
+            .collect::<Vec<_>>();
+        let mut tags = Tag::find_names_by_snippet_ids(conn, &ids)?;
+
+        Ok(snippets
+            .into_iter()
+            .map(|snippet| {
+                let snippet_tags = tags.remove(&snippet.id).unwrap_or_default();
+                Self::new(snippet, snippet_tags)
+            })
+            .collect())
+    }
+
+    /// Fills in which of the snippets the user has voted up or bookmarked.
+    /// Does nothing for anyone who isn't logged in.
+    pub fn load_for_user(
+        conn: &DbConn,
+        views: &mut [Self],
+        user: &MaybeUser,
+    ) -> Result<(), ModelError> {
+        let user_id = match user.user_id() {
+            Some(user_id) => user_id,
+            None => return Ok(()),
+        };
+        let ids = views.iter().map(|view| view.snippet.id).collect::<Vec<_>>();
+        let voted = SnippetVote::find_voted_snippet_ids(conn, user_id, &ids)?;
+        let bookmarked = Bookmark::find_bookmarked_snippet_ids(conn, user_id, &ids)?;
+
+        for view in views {
+            view.voted = Some(voted.contains(&view.snippet.id));
+            view.bookmarked = Some(bookmarked.contains(&view.snippet.id));
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds a snippet the user is allowed to see, which is any snippet for
+/// admins and only visible ones, in visible taxonomies, for everyone else.
+pub fn find_viewable_snippet(
+    conn: &DbConn,
+    user: &MaybeUser,
+    snippet_id: i32,
+) -> Result<Snippet, super::HandlerError> {
+    let snippet = Snippet::find_by_id(conn, snippet_id)?;
+
+    if (snippet.hidden || !snippet.is_published()) && !user.is_admin() {
+        return Err(super::HandlerError::NotFound);
+    }
+    find_visible_taxonomy(conn, user, &snippet.taxonomy)?;
+
+    Ok(snippet)
+}
+
+/// Whether a list filtered by several tags wants snippets with all of them or
+/// any of them. Defaults to all.
+#[derive(Debug, FromFormField)]
+pub enum TagMatchParam {
+    All,
+    Any,
+}
+
+impl From<TagMatchParam> for TagMatch {
+    fn from(param: TagMatchParam) -> Self {
+        match param {
+            TagMatchParam::All => TagMatch::All,
+            TagMatchParam::Any => TagMatch::Any,
+        }
+    }
+}
+
 /* #region GetSnippets */
 
-#[get("/snippets?<taxonomy>&<page>&<show_hidden>")]
+/// How many snippets come back at a time when the client doesn't say.
+const DEFAULT_PAGE_SIZE: i64 = 5;
+
+/// The most snippets a client can ask for at a time.
+const MAX_PAGE_SIZE: i64 = 50;
+
+/// Which snippets a list shows, by whether everyone can see them. Anything
+/// but visible is only for admins. Defaults to visible.
+#[derive(Debug, FromFormField)]
+pub enum VisibilityParam {
+    Visible,
+    Hidden,
+    All,
+}
+
+impl From<VisibilityParam> for Visibility {
+    fn from(param: VisibilityParam) -> Self {
+        match param {
+            VisibilityParam::Visible => Visibility::Visible,
+            VisibilityParam::Hidden => Visibility::Hidden,
+            VisibilityParam::All => Visibility::All,
+        }
+    }
+}
+
+/// What to sort a list of snippets by. Defaults to when they were shared.
+#[derive(Debug, FromFormField)]
+pub enum SortParam {
+    #[field(value = "shared_on")]
+    SharedOn,
+    #[field(value = "created_at")]
+    CreatedAt,
+    #[field(value = "updated_at")]
+    UpdatedAt,
+    #[field(value = "title")]
+    Title,
+    /// Most votes first.
+    #[field(value = "top")]
+    Top,
+    /// Most votes first, counting recent votes for more.
+    #[field(value = "hot")]
+    Hot,
+}
+
+impl From<SortParam> for SnippetSort {
+    fn from(param: SortParam) -> Self {
+        match param {
+            SortParam::SharedOn => SnippetSort::SharedOn,
+            SortParam::CreatedAt => SnippetSort::CreatedAt,
+            SortParam::UpdatedAt => SnippetSort::UpdatedAt,
+            SortParam::Title => SnippetSort::Title,
+            SortParam::Top => SnippetSort::Top,
+            SortParam::Hot => SnippetSort::Hot,
+        }
+    }
+}
+
+/// The query string of `get_snippets`.
+#[derive(Debug, FromForm)]
+pub struct GetSnippetsQuery<'r> {
+    taxonomy: &'r str,
+    page: Option<i32>,
+    cursor: Option<&'r str>,
+    limit: Option<i64>,
+    sort: Option<SortParam>,
+
+    visibility: Option<VisibilityParam>,
+    /// The same as `visibility=all`, from before there was `visibility`.
+    show_hidden: Option<bool>,
+    tags: Vec<String>,
+    tag_match: Option<TagMatchParam>,
+    shared_by: Option<String>,
+    creator_id: Option<i32>,
+    shared_since: Option<&'r str>,
+    shared_until: Option<&'r str>,
+    icon: Option<String>,
+}
+
+/// Lists the snippets in a taxonomy, newest first unless `sort` says
+/// otherwise. Passing one or more `tags` narrows the list down to snippets
+/// with all of those tags, or with any of them when `tag_match=any`. The
+/// list can be narrowed down further by who shared the snippets, who posted
+/// them, when they were shared (`shared_since` up until `shared_until`, as
+/// dates or RFC 3339 times), and their icon. Admins can ask for hidden
+/// snippets with `visibility`.
+///
+/// Lists come a `limit` at a time. Pass the `nextCursor` from one response
+/// as the `cursor` of the next, with the same `sort`, to carry on from where
+/// it left off; without a `page` or `cursor` the list starts from the top.
+/// The older `page` parameter still works, but shifts along whenever a
+/// snippet is shared.
+#[get("/snippets?<query..>")]
 pub async fn get_snippets(
     user: MaybeUser,
     ctxt: &State<ApplicationContext>,
-    taxonomy: &str,
-    page: i32,
-    show_hidden: bool,
+    query: GetSnippetsQuery<'_>,
 ) -> Result<Json<GetSnippetsOutput>, super::HandlerError> {
     let conn = ctxt.db_pool.read().get()?;
-    let show_hidden = user.is_admin() && show_hidden;
-    let page_size = 5;
-
-    let snippets = GetSnippetsOutput::new(&conn, page, page_size, taxonomy, !show_hidden)?;
+    find_visible_taxonomy(&conn, &user, query.taxonomy)?;
+
+    let visibility = match (query.visibility, query.show_hidden) {
+        _ if !user.is_admin() => Visibility::Visible,
+        (Some(visibility), _) => visibility.into(),
+        (None, Some(true)) => Visibility::All,
+        (None, _) => Visibility::Visible,
+    };
+    let filter = SnippetFilter {
+        taxonomy: Some(query.taxonomy.to_owned()),
+        tags: query.tags,
+        tag_match: query.tag_match.map(TagMatch::from).unwrap_or(TagMatch::All),
+        visibility,
+        shared_by: query.shared_by,
+        creator_id: query.creator_id,
+        shared_since: query.shared_since.map(parse_date_param).transpose()?,
+        shared_until: query.shared_until.map(parse_date_param).transpose()?,
+        icon: query.icon,
+        bookmarked_by: None,
+    };
+    let sort = query
+        .sort
+        .map(SnippetSort::from)
+        .unwrap_or(SnippetSort::SharedOn);
+    let limit = page_size(query.limit);
+    let position = ListPosition::from_query(query.cursor, query.page, sort)?;
+
+    let mut snippets = GetSnippetsOutput::new(&conn, position, limit, &filter, sort)?;
+    snippets.load_for_user(&conn, &user)?;
 
     Ok(Json(snippets))
 }
 
+/// Reads a date from a query string, either a plain date, taken as midnight
+/// UTC, or an RFC 3339 time.
+fn parse_date_param(value: &str) -> Result<NaiveDateTime, super::HandlerError> {
+    if let Some(midnight) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+    {
+        return Ok(midnight);
+    }
+
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.naive_utc())
+        .map_err(|_| super::HandlerError::InvalidInput(format!("Invalid date {}", value)))
+}
+
+/// How many snippets to list at a time, given how many the client asked for.
+pub fn page_size(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// Where in a list of snippets to start from.
+pub enum ListPosition {
+    /// The newest snippets.
+    Start,
+
+    /// Just past the snippet a cursor was made from.
+    After(SnippetCursor),
+
+    /// The nth page, counting from zero.
+    Page(i32),
+}
+
+impl ListPosition {
+    /// Reads the `cursor` and `page` parameters of a list sorted the given
+    /// way. A cursor wins over a page, and has to have been made with the
+    /// same sort.
+    pub fn from_query(
+        cursor: Option<&str>,
+        page: Option<i32>,
+        sort: SnippetSort,
+    ) -> Result<Self, super::HandlerError> {
+        match (cursor, page) {
+            (Some(cursor), _) => match SnippetCursor::decode(cursor) {
+                Some(cursor) if cursor.sort() == sort => Ok(ListPosition::After(cursor)),
+                _ => Err(super::HandlerError::InvalidInput(
+                    "Invalid cursor".to_owned(),
+                )),
+            },
+            (None, Some(page)) => Ok(ListPosition::Page(page)),
+            (None, None) => Ok(ListPosition::Start),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetSnippetsOutput {
-    snippets: Vec<Snippet>,
-    current_page: i32,
+    snippets: Vec<SnippetView>,
+    /// Only given when the list was asked for by page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_page: Option<i32>,
     total_pages: i64,
+    /// Where the next page picks up, absent on the last page.
+    next_cursor: Option<String>,
 }
 
 impl GetSnippetsOutput {
+    pub fn new(
+        conn: &DbConn,
+        position: ListPosition,
+        limit: i64,
+        filter: &SnippetFilter,
+        sort: SnippetSort,
+    ) -> Result<Self, ModelError> {
+        let snippet_count = Snippet::count(conn, filter)?;
+        let total_pages = std::cmp::max((snippet_count as f32 / limit as f32).ceil() as i64, 1);
+
+        let (snippets, current_page, has_more, skipped) = match position {
+            ListPosition::Page(page) => {
+                let snippets = Snippet::find_filtered(conn, filter, sort, page.into(), limit)?;
+                let has_more = i64::from(page + 1) * limit < snippet_count;
+                (snippets, Some(page), has_more, i64::from(page) * limit)
+            }
+            ListPosition::Start | ListPosition::After(_) => {
+                let after = match &position {
+                    ListPosition::After(cursor) => Some(cursor),
+                    _ => None,
+                };
+                // ask for one more than wanted to find out if there's more
+                let mut snippets =
+                    Snippet::find_filtered_after(conn, filter, sort, after, limit + 1)?;
+                let has_more = snippets.len() as i64 > limit;
+                snippets.truncate(limit as usize);
+                let skipped = after.and_then(SnippetCursor::position).unwrap_or(0);
+                (snippets, None, has_more, skipped)
+            }
+        };
+
+        let next_cursor = match snippets.last() {
+            Some(last) if has_more => {
+                let position = skipped + snippets.len() as i64;
+                Some(SnippetCursor::after(last, sort, position).encode())
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            snippets: SnippetView::load_all(conn, snippets)?,
+            current_page,
+            total_pages,
+            next_cursor,
+        })
+    }
+
+    /// See `SnippetView::load_for_user`.
+    pub fn load_for_user(&mut self, conn: &DbConn, user: &MaybeUser) -> Result<(), ModelError> {
+        SnippetView::load_for_user(conn, &mut self.snippets, user)
+    }
+}
+
+/* #endregion */
+/* #region SearchSnippets */
+
+/// Full-text search across every taxonomy. Results are ranked by relevance
+/// rather than by date, and hidden snippets are only included for admins who
+/// ask for them, same as `get_snippets`.
+#[get("/snippets/search?<q>&<page>&<show_hidden>")]
+pub async fn search_snippets(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    q: &str,
+    page: Option<i32>,
+    show_hidden: Option<bool>,
+) -> Result<Json<SearchSnippetsOutput>, super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let show_hidden = user.is_admin() && show_hidden.unwrap_or(false);
+    let page = std::cmp::max(page.unwrap_or(0), 0);
+    let page_size = 5;
+
+    let results = SearchSnippetsOutput::new(&conn, page, page_size, q, !show_hidden)?;
+
+    Ok(Json(results))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSnippetsOutput {
+    results: Vec<SnippetSearchHit>,
+    current_page: i32,
+    total_pages: i64,
+}
+
+impl SearchSnippetsOutput {
     pub fn new(
         conn: &DbConn,
         page: i32,
         page_size: i32,
-        taxonomy: &str,
+        query: &str,
         visible_only: bool,
     ) -> Result<Self, ModelError> {
-        let snippets = Snippet::find_all_by_taxonomy(
-            conn,
-            visible_only,
-            taxonomy,
-            page.into(),
-            page_size.into(),
-        )?;
-        let snippet_count = Snippet::count(conn, visible_only, taxonomy)?;
-        let total_pages = std::cmp::max((snippet_count as f32 / page_size as f32).ceil() as i64, 1);
+        let results =
+            SnippetSearchHit::search(conn, visible_only, query, page.into(), page_size.into())?;
+        let result_count = SnippetSearchHit::count(conn, visible_only, query)?;
+        let total_pages = std::cmp::max((result_count as f32 / page_size as f32).ceil() as i64, 1);
 
         Ok(Self {
-            snippets: snippets,
+            results,
             current_page: page,
             total_pages,
         })
@@ -71,21 +447,72 @@ pub async fn get_snippet(
     snippet_id: i32,
 ) -> Result<Json<GetSnippetOutput>, super::HandlerError> {
     let conn = ctxt.db_pool.read().get()?;
-    let can_view_hidden = user.is_admin();
+    let snippet = find_viewable_snippet(&conn, &user, snippet_id)?;
 
-    let snippet = Snippet::find_by_id(&conn, snippet_id)?;
+    let mut snippet = SnippetView::load(&conn, snippet)?;
+    SnippetView::load_for_user(&conn, std::slice::from_mut(&mut snippet), &user)?;
 
-    if snippet.hidden && !can_view_hidden {
-        Err(super::HandlerError::NotFound)
+    Ok(Json(GetSnippetOutput { snippet }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSnippetOutput {
+    snippet: SnippetView,
+}
+
+/* #endregion */
+/* #region VoteSnippet */
+
+/// Votes a snippet up on behalf of the logged in user. Voting again changes
+/// nothing.
+#[put("/snippets/<snippet_id>/vote")]
+pub async fn vote_snippet(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<VoteSnippetOutput>, super::HandlerError> {
+    set_vote(&user, ctxt, snippet_id, true)
+}
+
+/// Takes back the logged in user's vote for a snippet, if they had one.
+#[delete("/snippets/<snippet_id>/vote")]
+pub async fn unvote_snippet(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<VoteSnippetOutput>, super::HandlerError> {
+    set_vote(&user, ctxt, snippet_id, false)
+}
+
+fn set_vote(
+    user: &MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+    voted: bool,
+) -> Result<Json<VoteSnippetOutput>, super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let user_id = user.user_id().ok_or(super::HandlerError::NotLoggedIn)?;
+    find_viewable_snippet(&conn, user, snippet_id)?;
+
+    if voted {
+        SnippetVote::vote(&conn, snippet_id, user_id)?;
     } else {
-        Ok(Json(GetSnippetOutput { snippet }))
+        SnippetVote::unvote(&conn, snippet_id, user_id)?;
     }
+    let snippet = Snippet::find_by_id(&conn, snippet_id)?;
+
+    Ok(Json(VoteSnippetOutput {
+        voted,
+        vote_count: snippet.vote_count,
+    }))
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct GetSnippetOutput {
-    snippet: Snippet,
+pub struct VoteSnippetOutput {
+    voted: bool,
+    vote_count: i32,
 }
 
 /* #endregion */
@@ -97,23 +524,53 @@ pub async fn create_snippet(
     ctxt: &State<ApplicationContext>,
     input: Json<CreateSnippetInput>,
 ) -> Result<Json<CreateSnippetOutput>, super::HandlerError> {
+    let taxonomy = {
+        let conn = ctxt.db_pool.read().get()?;
+        let taxonomy = require_taxonomy(&conn, &input.taxonomy)?;
+        if let Some(icon) = &input.icon {
+            require_icon(&conn, icon)?;
+        }
+
+        if !input.allow_duplicate {
+            if let Some(existing) = Snippet::find_by_canonical_href(&conn, &input.href)? {
+                return Err(super::HandlerError::DuplicateSnippet(existing.id));
+            }
+        }
+
+        taxonomy
+    };
+
+    let icon = match &input.icon {
+        Some(icon) => icon.clone(),
+        None => favicons::suggest_icon(ctxt, &input.href)
+            .await?
+            .unwrap_or(taxonomy.default_icon),
+    };
+
     let conn = ctxt.db_pool.read().get()?;
+    let snippet = conn.transaction::<Snippet, ModelError, _>(|| {
+        let snippet = Snippet::create(
+            &conn,
+            user.user.0.id,
+            &input.taxonomy,
+            input.hidden,
+            &icon,
+            &input.title,
+            &input.shared_by,
+            &input.shared_on.naive_utc(),
+            &input.summary,
+            &input.description,
+            &input.href,
+            input.publish_at.map(|t| t.naive_utc()).as_ref(),
+        )?;
+        Tag::set_for_snippet(&conn, snippet.id, &input.tags)?;
 
-    let snippet = Snippet::create(
-        &conn,
-        user.user.0.id,
-        &input.taxonomy,
-        input.hidden,
-        &input.icon,
-        &input.title,
-        &input.shared_by,
-        &input.shared_on.naive_utc(),
-        &input.summary,
-        &input.description,
-        &input.href,
-    )?;
+        Ok(snippet)
+    })?;
 
-    Ok(Json(CreateSnippetOutput { snippet }))
+    Ok(Json(CreateSnippetOutput {
+        snippet: SnippetView::load(&conn, snippet)?,
+    }))
 }
 
 #[derive(Debug, Deserialize)]
@@ -122,18 +579,27 @@ pub struct CreateSnippetInput {
     taxonomy: String,
     hidden: bool,
     title: String,
-    icon: String,
+    /// When left out, the icon rules pick one from the link's site, then the
+    /// site's own favicon is tried, then the taxonomy's default icon.
+    icon: Option<String>,
     shared_by: String,
     shared_on: DateTime<FixedOffset>,
     summary: String,
     description: String,
     href: String,
+    /// Keeps the snippet hidden until this time, if given.
+    publish_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Shares the link even if it has been shared before.
+    #[serde(default)]
+    allow_duplicate: bool,
 }
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateSnippetOutput {
-    snippet: Snippet,
+    snippet: SnippetView,
 }
 
 /* #endregion */
@@ -141,14 +607,20 @@ pub struct CreateSnippetOutput {
 
 #[put("/snippets/<snippet_id>", data = "<input>")]
 pub async fn update_snippet(
-    _user: AdminOnly,
+    user: AdminOnly,
     ctxt: &State<ApplicationContext>,
     snippet_id: i32,
     input: Json<UpdateSnippetInput>,
 ) -> Result<Json<UpdateSnippetOutput>, super::HandlerError> {
     let conn = ctxt.db_pool.read().get()?;
     let mut snippet = Snippet::find_by_id(&conn, snippet_id)?;
+    require_taxonomy(&conn, &input.taxonomy)?;
+    // snippets from before icons were checked keep theirs until it's changed
+    if input.icon != snippet.icon {
+        require_icon(&conn, &input.icon)?;
+    }
 
+    snippet.taxonomy = input.taxonomy.clone();
     snippet.hidden = input.hidden;
     snippet.title = input.title.clone();
     snippet.icon = input.icon.clone();
@@ -157,8 +629,18 @@ pub async fn update_snippet(
     snippet.summary = input.summary.clone();
     snippet.description = input.description.clone();
     snippet.href = input.href.clone();
+    snippet.publish_at = input.publish_at.map(|t| t.naive_utc());
+
+    conn.transaction::<(), ModelError, _>(|| {
+        snippet.update(&conn, Some(user.user.0.id))?;
 
-    snippet.update(&conn)?;
+        // leave the tags be when the client doesn't know about them
+        if let Some(tags) = &input.tags {
+            Tag::set_for_snippet(&conn, snippet.id, tags)?;
+        }
+
+        Ok(())
+    })?;
 
     Ok(Json(UpdateSnippetOutput {}))
 }
@@ -175,6 +657,8 @@ pub struct UpdateSnippetInput {
     summary: String,
     description: String,
     href: String,
+    publish_at: Option<DateTime<FixedOffset>>,
+    tags: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -203,10 +687,141 @@ pub async fn delete_snippet(
 #[serde(rename_all = "camelCase")]
 pub struct DeleteSnippetOutput {}
 
+/* #endregion */
+/* #region PreviewSnippet */
+
+/// Fetches the page a link points to and suggests a title, summary, icon,
+/// and canonical href for a new snippet from what the page says about
+/// itself. Anything the page doesn't say is left out, so the output can be
+/// handed to create_snippet once the gaps are filled in. Only the icon rules
+/// are used to suggest an icon, since fetching the site's favicon would keep
+/// it, and a preview shouldn't change anything.
+#[post("/snippets/preview", data = "<input>")]
+pub async fn preview_snippet(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    input: Json<PreviewSnippetInput>,
+) -> Result<Json<PreviewSnippetOutput>, super::HandlerError> {
+    let metadata = ctxt
+        .page_metadata_client
+        .fetch(&input.href)
+        .await
+        .map_err(|e| super::HandlerError::InvalidInput(e.to_string()))?;
+    let href = metadata.canonical_url.unwrap_or_else(|| input.href.clone());
+
+    let conn = ctxt.db_pool.read().get()?;
+    let icon = match IconRule::suggest_icon(&conn, &href)? {
+        Some(icon) => Some(icon),
+        None => match &input.taxonomy {
+            Some(slug) => {
+                Taxonomy::find_by_slug(&conn, slug)?.map(|taxonomy| taxonomy.default_icon)
+            }
+            None => None,
+        },
+    };
+
+    Ok(Json(PreviewSnippetOutput {
+        title: metadata.title,
+        summary: metadata.summary,
+        icon,
+        href,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSnippetInput {
+    href: String,
+    /// The taxonomy the snippet is headed for, whose default icon is
+    /// suggested when the site has none of its own.
+    taxonomy: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSnippetOutput {
+    title: Option<String>,
+    summary: Option<String>,
+    icon: Option<String>,
+    /// The page's canonical URL, or where the link redirected to.
+    href: String,
+}
+
+/* #endregion */
+/* #region GetScheduledSnippets */
+
+/// Snippets waiting on their publish time, soonest first.
+#[get("/snippets/scheduled")]
+pub async fn get_scheduled_snippets(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetScheduledSnippetsOutput>, super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippets = SnippetView::load_all(&conn, Snippet::find_scheduled(&conn)?)?;
+
+    Ok(Json(GetScheduledSnippetsOutput { snippets }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetScheduledSnippetsOutput {
+    snippets: Vec<SnippetView>,
+}
+
+/* #endregion */
+/* #region GetTrash */
+
+/// Snippets which have been deleted but not yet purged, most recently
+/// deleted first.
+#[get("/snippets/trash")]
+pub async fn get_trash(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetTrashOutput>, super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippets = SnippetView::load_all(&conn, Snippet::find_all_deleted(&conn)?)?;
+
+    Ok(Json(GetTrashOutput { snippets }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTrashOutput {
+    snippets: Vec<SnippetView>,
+}
+
+/* #endregion */
+/* #region RestoreSnippet */
+
+/// Takes a snippet back out of the trash.
+#[post("/snippets/trash/<snippet_id>/restore")]
+pub async fn restore_snippet(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    snippet_id: i32,
+) -> Result<Json<RestoreSnippetOutput>, super::HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let snippet =
+        Snippet::find_deleted_by_id(&conn, snippet_id)?.ok_or(super::HandlerError::NotFound)?;
+
+    // its taxonomy may have been deleted while it was in the trash
+    require_taxonomy(&conn, &snippet.taxonomy)?;
+
+    snippet.restore(&conn)?;
+
+    Ok(Json(RestoreSnippetOutput {}))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreSnippetOutput {}
+
 /* #endregion */
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
     use chrono::{DateTime, FixedOffset};
     use rocket::serde::Deserialize;
 
@@ -227,4 +842,30 @@ mod tests {
             deserialized.js_date.to_string()
         );
     }
+
+    #[test]
+    fn test_find_viewable_snippet_in_hidden_taxonomy() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let mut taxonomy = Taxonomy::find_by_slug(&conn, "links").unwrap().unwrap();
+        taxonomy.hidden = true;
+        taxonomy.update(&conn).unwrap();
+        let snippet = create_test_snippet(&conn, "https://example.com/");
+
+        let anyone = MaybeUser {
+            user: None,
+            permissions: Vec::new(),
+        };
+        assert!(matches!(
+            find_viewable_snippet(&conn, &anyone, snippet.id),
+            Err(crate::controllers::HandlerError::NotFound)
+        ));
+
+        let admin = MaybeUser {
+            user: None,
+            permissions: vec!["admin".to_owned()],
+        };
+        assert!(find_viewable_snippet(&conn, &admin, snippet.id).is_ok());
+    }
 }
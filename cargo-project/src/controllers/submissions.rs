@@ -0,0 +1,300 @@
+use super::{
+    icons::require_icon,
+    snippets::SnippetView,
+    taxonomies::{find_visible_taxonomy, require_taxonomy},
+    HandlerError,
+};
+use crate::{
+    application_context::ApplicationContext,
+    helpers::{admin_only::AdminOnly, maybe_user::MaybeUser},
+    models::{
+        submissions::{self, NewSubmission, Submission},
+        IconRule, Snippet, Tag,
+    },
+};
+use chrono::{DateTime, FixedOffset};
+use diesel::Connection;
+use reqwest::Url;
+use rocket::{get, post, serde::json::Json, FromFormField, State};
+use serde::{Deserialize, Serialize};
+
+/// Which submissions to list. Defaults to pending.
+#[derive(Debug, FromFormField)]
+pub enum SubmissionStatusParam {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl SubmissionStatusParam {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionStatusParam::Pending => submissions::PENDING,
+            SubmissionStatusParam::Approved => submissions::APPROVED,
+            SubmissionStatusParam::Rejected => submissions::REJECTED,
+        }
+    }
+}
+
+/// A submission is only reviewed once.
+fn find_pending_submission(
+    conn: &crate::db::DbConn,
+    submission_id: i32,
+) -> Result<Submission, HandlerError> {
+    let submission = Submission::find_by_id(conn, submission_id)?;
+    if !submission.is_pending() {
+        return Err(HandlerError::Conflict(
+            "This submission has already been reviewed".to_owned(),
+        ));
+    }
+
+    Ok(submission)
+}
+
+/* #region CreateSubmission */
+
+/// Puts a link in the queue for admins to look over. Anyone who's logged in
+/// can submit, and they're credited by their GitHub login.
+#[post("/submissions", data = "<input>")]
+pub async fn create_submission(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    input: Json<CreateSubmissionInput>,
+) -> Result<Json<CreateSubmissionOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let (submitter, github_user) = user.user.as_ref().ok_or(HandlerError::NotLoggedIn)?;
+
+    find_visible_taxonomy(&conn, &user, &input.taxonomy)
+        .map_err(|_| HandlerError::InvalidInput(format!("No such taxonomy {}", input.taxonomy)))?;
+    if input.title.trim().is_empty() {
+        return Err(HandlerError::InvalidInput("A title is required".to_owned()));
+    }
+    match Url::parse(&input.href) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+        _ => {
+            return Err(HandlerError::InvalidInput(
+                "Only http and https links can be submitted".to_owned(),
+            ))
+        }
+    }
+
+    if let Some(existing) = Snippet::find_by_canonical_href(&conn, &input.href)? {
+        return Err(HandlerError::DuplicateSnippet(existing.id));
+    }
+    if Submission::find_pending_by_canonical_href(&conn, &input.href)?.is_some() {
+        return Err(HandlerError::Conflict(
+            "This link has already been submitted".to_owned(),
+        ));
+    }
+
+    let submission = Submission::create(
+        &conn,
+        &NewSubmission {
+            submitter_id: submitter.id,
+            taxonomy: &input.taxonomy,
+            title: input.title.trim(),
+            shared_by: &github_user.login,
+            summary: &input.summary,
+            description: &input.description,
+            href: input.href.trim(),
+        },
+    )?;
+
+    Ok(Json(CreateSubmissionOutput { submission }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubmissionInput {
+    taxonomy: String,
+    title: String,
+    summary: String,
+    #[serde(default)]
+    description: String,
+    href: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubmissionOutput {
+    submission: Submission,
+}
+
+/* #endregion */
+/* #region GetSubmissions */
+
+/// The moderation queue, oldest first. Pass `status` to look back over
+/// submissions which were already approved or rejected.
+#[get("/submissions?<status>")]
+pub async fn get_submissions(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    status: Option<SubmissionStatusParam>,
+) -> Result<Json<GetSubmissionsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let status = status.unwrap_or(SubmissionStatusParam::Pending);
+    let submissions = Submission::find_by_status(&conn, status.as_str())?;
+
+    Ok(Json(GetSubmissionsOutput { submissions }))
+}
+
+/// Everything the logged in user has submitted, newest first, and how each
+/// one went.
+#[get("/submissions/mine")]
+pub async fn get_my_submissions(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetSubmissionsOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let (submitter, _) = user.user.as_ref().ok_or(HandlerError::NotLoggedIn)?;
+    let submissions = Submission::find_by_submitter_id(&conn, submitter.id)?;
+
+    Ok(Json(GetSubmissionsOutput { submissions }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetSubmissionsOutput {
+    submissions: Vec<Submission>,
+}
+
+/* #endregion */
+/* #region ApproveSubmission */
+
+/// Makes a snippet of a submission, crediting the submitter as its creator.
+/// Anything given in the input is used in place of what was submitted, so an
+/// admin can touch up a submission on the way through; send `{}` to take it
+/// as it is.
+#[post("/submissions/<submission_id>/approve", data = "<input>")]
+pub async fn approve_submission(
+    user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    submission_id: i32,
+    input: Json<ApproveSubmissionInput>,
+) -> Result<Json<ApproveSubmissionOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    // looking the submission up in the same transaction as approving it means
+    // approving twice at once can't make two snippets
+    let snippet = conn.transaction::<Snippet, HandlerError, _>(|| {
+        let submission = find_pending_submission(&conn, submission_id)?;
+
+        let taxonomy = require_taxonomy(
+            &conn,
+            input.taxonomy.as_ref().unwrap_or(&submission.taxonomy),
+        )?;
+        let href = input.href.as_ref().unwrap_or(&submission.href);
+        let icon = match &input.icon {
+            Some(icon) => {
+                require_icon(&conn, icon)?;
+                icon.clone()
+            }
+            None => IconRule::suggest_icon(&conn, href)?
+                .unwrap_or_else(|| taxonomy.default_icon.clone()),
+        };
+
+        // the link may have been shared some other way since it was submitted
+        if !input.allow_duplicate {
+            if let Some(existing) = Snippet::find_by_canonical_href(&conn, href)? {
+                return Err(HandlerError::DuplicateSnippet(existing.id));
+            }
+        }
+
+        let snippet = Snippet::create(
+            &conn,
+            submission.submitter_id,
+            &taxonomy.slug,
+            input.hidden,
+            &icon,
+            input.title.as_ref().unwrap_or(&submission.title),
+            input.shared_by.as_ref().unwrap_or(&submission.shared_by),
+            &input
+                .shared_on
+                .map(|t| t.naive_utc())
+                .unwrap_or(submission.created_at),
+            input.summary.as_ref().unwrap_or(&submission.summary),
+            input
+                .description
+                .as_ref()
+                .unwrap_or(&submission.description),
+            href,
+            input.publish_at.map(|t| t.naive_utc()).as_ref(),
+        )?;
+        Tag::set_for_snippet(&conn, snippet.id, &input.tags)?;
+        submission.approve(&conn, user.user.0.id, snippet.id)?;
+
+        Ok(snippet)
+    })?;
+
+    Ok(Json(ApproveSubmissionOutput {
+        snippet: SnippetView::load(&conn, snippet)?,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveSubmissionInput {
+    taxonomy: Option<String>,
+    #[serde(default)]
+    hidden: bool,
+    title: Option<String>,
+    /// Picked from the link, or the taxonomy's default, when left out.
+    icon: Option<String>,
+    shared_by: Option<String>,
+    /// When the link was submitted, when left out.
+    shared_on: Option<DateTime<FixedOffset>>,
+    summary: Option<String>,
+    description: Option<String>,
+    href: Option<String>,
+    publish_at: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Approves the link even if it has been shared some other way since.
+    #[serde(default)]
+    allow_duplicate: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveSubmissionOutput {
+    snippet: SnippetView,
+}
+
+/* #endregion */
+/* #region RejectSubmission */
+
+/// Turns a submission down. The reason is shown to the submitter.
+#[post("/submissions/<submission_id>/reject", data = "<input>")]
+pub async fn reject_submission(
+    user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    submission_id: i32,
+    input: Json<RejectSubmissionInput>,
+) -> Result<Json<RejectSubmissionOutput>, HandlerError> {
+    if input.reason.trim().is_empty() {
+        return Err(HandlerError::InvalidInput(
+            "A reason is required".to_owned(),
+        ));
+    }
+
+    let conn = ctxt.db_pool.read().get()?;
+    conn.transaction::<(), HandlerError, _>(|| {
+        let submission = find_pending_submission(&conn, submission_id)?;
+        submission.reject(&conn, user.user.0.id, input.reason.trim())?;
+
+        Ok(())
+    })?;
+
+    Ok(Json(RejectSubmissionOutput {}))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectSubmissionInput {
+    reason: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RejectSubmissionOutput {}
+
+/* #endregion */
@@ -0,0 +1,230 @@
+use super::{icons::require_icon, HandlerError};
+use crate::{
+    application_context::ApplicationContext,
+    db::DbConn,
+    helpers::{admin_only::AdminOnly, maybe_user::MaybeUser},
+    models::{taxonomies::is_valid_slug, Taxonomy},
+};
+use rocket::{delete, get, post, put, serde::json::Json, State};
+use serde::{Deserialize, Serialize};
+
+/// A taxonomy as the API presents it, along with how many snippets the
+/// person asking can see in it.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxonomyView {
+    #[serde(flatten)]
+    taxonomy: Taxonomy,
+    snippet_count: i64,
+}
+
+/// Finds a taxonomy that the user is allowed to see, which is any of them for
+/// admins and only the visible ones for everyone else. Hidden taxonomies are
+/// reported as missing rather than forbidden so as to not leak their
+/// existence.
+pub fn find_visible_taxonomy(
+    conn: &DbConn,
+    user: &MaybeUser,
+    slug: &str,
+) -> Result<Taxonomy, HandlerError> {
+    match Taxonomy::find_by_slug(conn, slug)? {
+        Some(taxonomy) if !taxonomy.hidden || user.is_admin() => Ok(taxonomy),
+        _ => Err(HandlerError::NotFound),
+    }
+}
+
+/// Makes sure that a snippet is being filed under a taxonomy that exists, so
+/// that a typo doesn't quietly create a new section of the site.
+pub fn require_taxonomy(conn: &DbConn, slug: &str) -> Result<Taxonomy, HandlerError> {
+    Taxonomy::find_by_slug(conn, slug)?
+        .ok_or_else(|| HandlerError::InvalidInput(format!("No such taxonomy {}", slug)))
+}
+
+/* #region GetTaxonomies */
+
+#[get("/taxonomies")]
+pub async fn get_taxonomies(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+) -> Result<Json<GetTaxonomiesOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let visible_only = !user.is_admin();
+
+    let mut counts = Taxonomy::count_snippets(&conn, visible_only)?;
+    let taxonomies = Taxonomy::find_all(&conn, visible_only)?
+        .into_iter()
+        .map(|taxonomy| TaxonomyView {
+            snippet_count: counts.remove(&taxonomy.slug).unwrap_or(0),
+            taxonomy,
+        })
+        .collect();
+
+    Ok(Json(GetTaxonomiesOutput { taxonomies }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTaxonomiesOutput {
+    taxonomies: Vec<TaxonomyView>,
+}
+
+/* #endregion */
+/* #region GetTaxonomy */
+
+#[get("/taxonomies/<slug>")]
+pub async fn get_taxonomy(
+    user: MaybeUser,
+    ctxt: &State<ApplicationContext>,
+    slug: &str,
+) -> Result<Json<GetTaxonomyOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let taxonomy = find_visible_taxonomy(&conn, &user, slug)?;
+    let snippet_count = Taxonomy::count_snippets(&conn, !user.is_admin())?
+        .remove(&taxonomy.slug)
+        .unwrap_or(0);
+
+    Ok(Json(GetTaxonomyOutput {
+        taxonomy: TaxonomyView {
+            taxonomy,
+            snippet_count,
+        },
+    }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetTaxonomyOutput {
+    taxonomy: TaxonomyView,
+}
+
+/* #endregion */
+/* #region CreateTaxonomy */
+
+#[post("/taxonomies", data = "<input>")]
+pub async fn create_taxonomy(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    input: Json<CreateTaxonomyInput>,
+) -> Result<Json<CreateTaxonomyOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+
+    if !is_valid_slug(&input.slug) {
+        return Err(HandlerError::InvalidInput(
+            "Slugs may only contain lowercase letters, digits, and dashes".to_owned(),
+        ));
+    }
+
+    if Taxonomy::find_by_slug(&conn, &input.slug)?.is_some() {
+        return Err(HandlerError::Conflict(format!(
+            "A taxonomy with the slug {} already exists",
+            input.slug
+        )));
+    }
+    require_icon(&conn, &input.default_icon)?;
+
+    let taxonomy = Taxonomy::create(
+        &conn,
+        &input.slug,
+        &input.display_name,
+        &input.description,
+        input.sort_order,
+        &input.default_icon,
+        input.hidden,
+    )?;
+
+    Ok(Json(CreateTaxonomyOutput { taxonomy }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTaxonomyInput {
+    slug: String,
+    display_name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    sort_order: i32,
+    default_icon: String,
+    #[serde(default)]
+    hidden: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTaxonomyOutput {
+    taxonomy: Taxonomy,
+}
+
+/* #endregion */
+/* #region UpdateTaxonomy */
+
+#[put("/taxonomies/<slug>", data = "<input>")]
+pub async fn update_taxonomy(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    slug: &str,
+    input: Json<UpdateTaxonomyInput>,
+) -> Result<Json<UpdateTaxonomyOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let mut taxonomy = Taxonomy::find_by_slug(&conn, slug)?.ok_or(HandlerError::NotFound)?;
+    require_icon(&conn, &input.default_icon)?;
+
+    taxonomy.display_name = input.display_name.clone();
+    taxonomy.description = input.description.clone();
+    taxonomy.sort_order = input.sort_order;
+    taxonomy.default_icon = input.default_icon.clone();
+    taxonomy.hidden = input.hidden;
+
+    taxonomy.update(&conn)?;
+
+    Ok(Json(UpdateTaxonomyOutput {}))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTaxonomyInput {
+    display_name: String,
+    description: String,
+    sort_order: i32,
+    default_icon: String,
+    hidden: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateTaxonomyOutput {}
+
+/* #endregion */
+/* #region DeleteTaxonomy */
+
+/// Deletes a taxonomy, so long as nothing is filed under it anymore. Move or
+/// delete its snippets first.
+#[delete("/taxonomies/<slug>")]
+pub async fn delete_taxonomy(
+    _user: AdminOnly,
+    ctxt: &State<ApplicationContext>,
+    slug: &str,
+) -> Result<Json<DeleteTaxonomyOutput>, HandlerError> {
+    let conn = ctxt.db_pool.read().get()?;
+    let taxonomy = Taxonomy::find_by_slug(&conn, slug)?.ok_or(HandlerError::NotFound)?;
+
+    let snippet_count = Taxonomy::count_snippets(&conn, false)?
+        .remove(&taxonomy.slug)
+        .unwrap_or(0);
+    if snippet_count > 0 {
+        return Err(HandlerError::Conflict(format!(
+            "The taxonomy {} still has {} snippets in it",
+            taxonomy.slug, snippet_count
+        )));
+    }
+
+    taxonomy.delete(&conn)?;
+
+    Ok(Json(DeleteTaxonomyOutput {}))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteTaxonomyOutput {}
+
+/* #endregion */
@@ -1,3 +1,4 @@
+use crate::models::Snippet;
 use diesel::{
     r2d2::{ConnectionManager, Pool, PooledConnection},
     SqliteConnection,
@@ -27,4 +28,25 @@ pub fn get_pool(db_path: &str, maxconns: u32) -> DbPool {
 pub fn migrate_db(pool: &DbPool) {
     let conn = pool.read().get().unwrap();
     embedded_migrations::run_with_output(&conn, &mut std::io::stdout()).unwrap();
+
+    // canonical hrefs follow rules which live in the app rather than in sql,
+    // so the app brings them up to date, both for rows from before there
+    // were canonical hrefs and for when the rules change.
+    let refreshed = Snippet::refresh_canonical_hrefs(&conn).unwrap();
+    if refreshed > 0 {
+        println!("Refreshed {} canonical hrefs", refreshed);
+    }
+}
+
+/// An in-memory database with every migration applied, for tests. There is
+/// only the one connection, since each new connection to `:memory:` would be
+/// its own empty database.
+#[cfg(test)]
+pub fn test_pool() -> DbPool {
+    let pool = get_pool(":memory:", 1);
+    let conn = pool.read().get().unwrap();
+    embedded_migrations::run(&conn).unwrap();
+    drop(conn);
+
+    pool
 }
@@ -0,0 +1,257 @@
+//! Turns the links people share on Discord into draft snippets. Reads the
+//! JSON that DiscordChatExporter writes for a channel, pulls every link out
+//! of every message, and files the ones we haven't seen before as hidden
+//! snippets for an admin to go over, credited to whoever posted them.
+
+use crate::{
+    db::DbConn,
+    models::{IconRule, ModelError, Snippet},
+};
+use chrono::{DateTime, FixedOffset};
+use diesel::Connection;
+use reqwest::Url;
+use serde::Deserialize;
+
+/// Characters which end a sentence, or Discord's markup, more often than a
+/// URL, and so are left off the end of one found in a message.
+const TRAILING_PUNCTUATION: &[char] = &[
+    '.', ',', '!', '?', ';', ':', '\'', '"', '*', '_', '~', '|', '>',
+];
+
+/// A channel as DiscordChatExporter exports it, or the parts of it we use.
+#[derive(Debug, Deserialize)]
+pub struct DiscordExport {
+    pub messages: Vec<DiscordMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordMessage {
+    pub timestamp: DateTime<FixedOffset>,
+    pub content: String,
+    pub author: DiscordAuthor,
+
+    /// The previews Discord made of links in the message.
+    #[serde(default)]
+    pub embeds: Vec<DiscordEmbed>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordAuthor {
+    /// The author's username.
+    pub name: String,
+
+    /// What the author goes by on the server, if it differs.
+    #[serde(default)]
+    pub nickname: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscordEmbed {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// How an import went.
+#[derive(Debug, Default)]
+pub struct DiscordImportSummary {
+    /// The drafts made, in the order the links were shared.
+    pub created: Vec<Snippet>,
+
+    /// How many links were passed over for already having a snippet.
+    pub skipped: usize,
+}
+
+/// Makes a hidden draft snippet in the taxonomy for every link in the export
+/// which no snippet links to yet, including those in the trash. Discord's
+/// preview of the link, if it made one, fills in the title and summary;
+/// otherwise the title is the link itself and the summary is the message.
+pub fn import(
+    conn: &DbConn,
+    export: &DiscordExport,
+    the_taxonomy: &str,
+    default_icon: &str,
+    creator_id: i32,
+) -> Result<DiscordImportSummary, ModelError> {
+    let mut summary = DiscordImportSummary::default();
+    let icon_rules = IconRule::find_all(conn)?;
+
+    conn.transaction::<(), ModelError, _>(|| {
+        for message in &export.messages {
+            for href in extract_urls(&message.content) {
+                // links shared again later are caught here too, since the
+                // first one has a snippet by then
+                if Snippet::is_href_known(conn, &href)? {
+                    summary.skipped += 1;
+                    continue;
+                }
+
+                let embed = message
+                    .embeds
+                    .iter()
+                    .find(|embed| embed.url.as_deref() == Some(href.as_str()));
+                let title = embed
+                    .and_then(|embed| embed.title.clone())
+                    .unwrap_or_else(|| href.clone());
+                let the_summary = embed
+                    .and_then(|embed| embed.description.clone())
+                    .unwrap_or_else(|| message.content.clone());
+                let shared_by = message
+                    .author
+                    .nickname
+                    .as_ref()
+                    .unwrap_or(&message.author.name);
+
+                summary.created.push(Snippet::create(
+                    conn,
+                    creator_id,
+                    the_taxonomy,
+                    true,
+                    IconRule::pick(&icon_rules, &href).unwrap_or(default_icon),
+                    &title,
+                    shared_by,
+                    &message.timestamp.naive_utc(),
+                    &the_summary,
+                    "",
+                    &href,
+                    None,
+                )?);
+            }
+        }
+
+        Ok(())
+    })?;
+
+    Ok(summary)
+}
+
+/// Finds the http and https links in a message, in order. Discord has no
+/// markup for links beyond `<...>` to hide the preview and `[text](...)`, so
+/// anything starting with a scheme is taken to run until whitespace, less
+/// any punctuation which was more likely the end of the sentence.
+pub fn extract_urls(content: &str) -> Vec<String> {
+    content
+        .split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("https://").or_else(|| word.find("http://"))?;
+            let mut url = &word[start..];
+
+            loop {
+                let trimmed = url.trim_end_matches(TRAILING_PUNCTUATION);
+                // a closing paren is part of the URL only if it has an
+                // opening one, as on Wikipedia
+                let trimmed = match trimmed.strip_suffix(')') {
+                    Some(rest) if rest.matches('(').count() <= rest.matches(')').count() => rest,
+                    _ => trimmed,
+                };
+                if trimmed == url {
+                    break;
+                }
+                url = trimmed;
+            }
+
+            Url::parse(url).ok()?;
+            Some(url.to_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[test]
+    fn test_extract_urls() {
+        assert_eq!(
+            vec![
+                "https://example.com/a",
+                "http://example.com/b?c=d",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "https://example.com/e",
+            ],
+            extract_urls(
+                "look: https://example.com/a, and <http://example.com/b?c=d>! also \
+                 (https://en.wikipedia.org/wiki/Rust_(programming_language)) and \
+                 [this](https://example.com/e). not https:// though"
+            )
+        );
+        assert!(extract_urls("nothing to see here").is_empty());
+    }
+
+    #[test]
+    fn test_import() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let export: DiscordExport = serde_json::from_str(
+            r#"{
+                "guild": { "id": "1", "name": "iDevGames" },
+                "channel": { "id": "2", "name": "links" },
+                "messages": [
+                    {
+                        "id": "3",
+                        "timestamp": "2021-05-01T12:00:00.123+02:00",
+                        "content": "this is neat https://github.com/idevgames",
+                        "author": { "id": "4", "name": "someone", "nickname": "Someone" },
+                        "embeds": [
+                            {
+                                "title": "iDevGames",
+                                "url": "https://github.com/idevgames",
+                                "description": "The iDevGames organization"
+                            }
+                        ]
+                    },
+                    {
+                        "id": "5",
+                        "timestamp": "2021-05-02T12:00:00+00:00",
+                        "content": "https://www.github.com/idevgames/ again, and https://example.com/",
+                        "author": { "id": "6", "name": "someone_else", "nickname": null }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let summary = import(&conn, &export, "links", "safari.png", 1).unwrap();
+        assert_eq!(1, summary.skipped);
+        assert_eq!(2, summary.created.len());
+
+        let first = &summary.created[0];
+        assert!(first.hidden);
+        assert_eq!(
+            (
+                "iDevGames",
+                "The iDevGames organization",
+                "Someone",
+                "github.png"
+            ),
+            (
+                first.title.as_str(),
+                first.summary.as_str(),
+                first.shared_by.as_str(),
+                first.icon.as_str()
+            )
+        );
+        assert_eq!("2021-05-01 10:00:00.123", first.shared_on.to_string());
+
+        let second = &summary.created[1];
+        assert_eq!(
+            ("https://example.com/", "someone_else", "safari.png"),
+            (
+                second.title.as_str(),
+                second.shared_by.as_str(),
+                second.icon.as_str()
+            )
+        );
+
+        // nothing is new the second time around
+        let summary = import(&conn, &export, "links", "safari.png", 1).unwrap();
+        assert_eq!((0, 3), (summary.created.len(), summary.skipped));
+    }
+}
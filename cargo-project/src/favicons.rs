@@ -0,0 +1,102 @@
+//! Picks icons for links nobody picked one for. The icon rules come first,
+//! and failing those we go and get the site's own favicon, which is kept as
+//! an icon along with a rule for its host, so each site is only fetched once.
+
+use crate::{
+    application_context::ApplicationContext,
+    models::{Icon, IconKind, IconRule, ModelError},
+};
+use reqwest::Url;
+
+/// The biggest favicon worth keeping. Most are a few kilobytes.
+const MAX_FAVICON_BYTES: usize = 64 * 1024;
+
+/// The icon for a link, if the rules have one or the site has a favicon we
+/// can use. Problems fetching or keeping favicons are logged rather than
+/// returned, since there's always the taxonomy's default icon to fall back
+/// on.
+pub async fn suggest_icon(
+    ctxt: &ApplicationContext,
+    href: &str,
+) -> Result<Option<String>, ModelError> {
+    // the pool may only have the one connection, so none is held while
+    // waiting on another site
+    {
+        let conn = ctxt.db_pool.read().get()?;
+        if let Some(icon) = IconRule::suggest_icon(&conn, href)? {
+            return Ok(Some(icon));
+        }
+    }
+
+    let host = match Url::parse(href)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+    {
+        Some(host) => host.trim_start_matches("www.").to_owned(),
+        None => return Ok(None),
+    };
+    if !IconRule::is_valid_pattern(&host) {
+        return Ok(None);
+    }
+
+    // someone may have added the site's icon by hand already, under its name
+    {
+        let conn = ctxt.db_pool.read().get()?;
+        if let Some(icon) = Icon::find_by_name(&conn, &host)? {
+            return Ok(Some(icon.filename));
+        }
+    }
+
+    let bytes = match ctxt
+        .page_metadata_client
+        .fetch_favicon(href, MAX_FAVICON_BYTES)
+        .await
+    {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::warn!("Couldn't fetch the favicon for {}: {}", href, e);
+            return Ok(None);
+        }
+    };
+    // icons are served from our own domain, so nothing which could carry
+    // script, like an SVG, is taken from another site
+    let kind = match IconKind::sniff(&bytes).filter(|kind| kind.is_raster()) {
+        Some(kind) => kind,
+        None => {
+            log::warn!("The favicon for {} isn't an image we can use", href);
+            return Ok(None);
+        }
+    };
+
+    let filename = format!("favicon-{}.{}", host.replace('.', "-"), kind.extension());
+    if !Icon::is_valid_filename(&filename) {
+        return Ok(None);
+    }
+
+    let conn = ctxt.db_pool.read().get()?;
+    // another snippet may have fetched it in the meantime
+    if Icon::find_by_filename(&conn, &filename)?.is_none() {
+        if let Err(e) = std::fs::create_dir_all(&ctxt.icon_dir)
+            .and_then(|_| std::fs::write(ctxt.icon_dir.join(&filename), &bytes))
+        {
+            log::error!("Couldn't save the favicon for {}: {}", href, e);
+            return Ok(None);
+        }
+        if let Err(e) = Icon::create(&conn, &host, &filename, kind.content_type()) {
+            // such as when another snippet beat us to it after all
+            if Icon::find_by_filename(&conn, &filename)?.is_none() {
+                log::warn!("Couldn't add the favicon for {} as an icon: {}", href, e);
+                return Ok(None);
+            }
+        }
+    }
+    if IconRule::suggest_icon(&conn, href)?.is_none() {
+        // the icon is there to use either way, only the next snippet for
+        // the site will have to look again
+        if let Err(e) = IconRule::create(&conn, &host, &filename) {
+            log::warn!("Couldn't add an icon rule for {}: {}", host, e);
+        }
+    }
+
+    Ok(Some(filename))
+}
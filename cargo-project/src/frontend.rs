@@ -0,0 +1,272 @@
+//! Serves the React app's build, so that a single binary can run the whole
+//! site without nginx in front of it. The build is read from a directory
+//! given at startup or, with the `embed-frontend` feature, built right into
+//! the binary.
+
+use crate::helpers::accept_encoding::AcceptEncoding;
+use rocket::{
+    http::{ContentType, Header},
+    request::Request,
+    response::{self, Responder, Response},
+    tokio::fs,
+};
+use scraper::{Html, Selector};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[cfg(feature = "embed-frontend")]
+static EMBEDDED: include_dir::Dir<'_> =
+    include_dir::include_dir!("$CARGO_MANIFEST_DIR/../react-project/build");
+
+/// For files with a hash of their contents in the name, which never change.
+const CACHE_FOREVER: &str = "public, max-age=31536000, immutable";
+
+/// For everything else, which could change with any deploy.
+const CACHE_BRIEFLY: &str = "public, max-age=3600";
+
+/// For index.html, which has to be current to load the current app.
+const CACHE_NEVER: &str = "no-cache";
+
+#[derive(Clone, Debug)]
+pub enum FrontendSource {
+    /// A build on disk, such as `react-project/build`.
+    Dir(PathBuf),
+
+    /// The build in `react-project/build` as it was when the binary was
+    /// compiled.
+    #[cfg(feature = "embed-frontend")]
+    Embedded,
+}
+
+#[derive(Debug, Error)]
+pub enum FrontendError {
+    #[error("Couldn't read index.html from {0:?} with error {1}")]
+    NoIndex(PathBuf, std::io::Error),
+
+    #[cfg(feature = "embed-frontend")]
+    #[error("The embedded build has no index.html")]
+    NoEmbeddedIndex,
+}
+
+#[derive(Clone, Debug)]
+pub struct Frontend {
+    source: FrontendSource,
+
+    /// The script and stylesheet tags from the app's index.html, which the
+    /// server-rendered pages include so that the app takes over from them.
+    pub app_tags: String,
+}
+
+impl Frontend {
+    /// The build from the source, which has to at least have an index.html.
+    pub fn new(source: FrontendSource) -> Result<Self, FrontendError> {
+        let index_html = match &source {
+            FrontendSource::Dir(dir) => std::fs::read_to_string(dir.join("index.html"))
+                .map_err(|e| FrontendError::NoIndex(dir.clone(), e))?,
+            #[cfg(feature = "embed-frontend")]
+            FrontendSource::Embedded => EMBEDDED
+                .get_file("index.html")
+                .and_then(|file| file.contents_utf8())
+                .ok_or(FrontendError::NoEmbeddedIndex)?
+                .to_owned(),
+        };
+
+        Ok(Self {
+            app_tags: find_app_tags(&index_html),
+            source,
+        })
+    }
+
+    /// The build embedded in the binary, if it was compiled with one.
+    pub fn embedded() -> Option<Result<Self, FrontendError>> {
+        #[cfg(feature = "embed-frontend")]
+        return Some(Self::new(FrontendSource::Embedded));
+
+        #[cfg(not(feature = "embed-frontend"))]
+        None
+    }
+
+    /// A file from the build, by its path relative to the build. A brotli
+    /// or gzip version of the file is preferred when there is one, sitting
+    /// next to it as `.br` or `.gz`, and the client takes it.
+    pub async fn open(&self, path: &str, encodings: &AcceptEncoding) -> Option<StaticFile> {
+        let content_type = content_type(path);
+        let cache_control = cache_control(path);
+
+        let variants = [
+            (encodings.brotli, ".br", Some("br")),
+            (encodings.gzip, ".gz", Some("gzip")),
+            (true, "", None),
+        ];
+        for (accepted, suffix, encoding) in variants.iter().copied() {
+            if !accepted {
+                continue;
+            }
+            if let Some(body) = self.read(&format!("{}{}", path, suffix)).await {
+                return Some(StaticFile {
+                    body,
+                    content_type: content_type.clone(),
+                    encoding,
+                    cache_control,
+                });
+            }
+        }
+
+        None
+    }
+
+    async fn read(&self, path: &str) -> Option<StaticFileBody> {
+        match &self.source {
+            FrontendSource::Dir(dir) => read_file(&dir.join(path)).await,
+            #[cfg(feature = "embed-frontend")]
+            FrontendSource::Embedded => EMBEDDED
+                .get_file(path)
+                .map(|file| StaticFileBody::Bytes(file.contents())),
+        }
+    }
+}
+
+enum StaticFileBody {
+    File(fs::File),
+    #[cfg(feature = "embed-frontend")]
+    Bytes(&'static [u8]),
+}
+
+/// A file served as-is, with caching headers to match what it is.
+pub struct StaticFile {
+    body: StaticFileBody,
+    content_type: ContentType,
+    /// The `Content-Encoding` the file is stored in, if any.
+    encoding: Option<&'static str>,
+    cache_control: &'static str,
+}
+
+impl StaticFile {
+    /// A file on disk, such as an uploaded icon.
+    pub async fn open(path: &Path) -> Option<Self> {
+        let body = read_file(path).await?;
+        let path = path.to_string_lossy();
+
+        Some(Self {
+            body,
+            content_type: content_type(&path),
+            encoding: None,
+            cache_control: cache_control(&path),
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for StaticFile {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response
+            .header(self.content_type)
+            .header(Header::new("Cache-Control", self.cache_control))
+            .header(Header::new("Vary", "Accept-Encoding"));
+        if let Some(encoding) = self.encoding {
+            response.header(Header::new("Content-Encoding", encoding));
+        }
+        match self.body {
+            StaticFileBody::File(file) => response.sized_body(None, file),
+            #[cfg(feature = "embed-frontend")]
+            StaticFileBody::Bytes(bytes) => {
+                response.sized_body(bytes.len(), std::io::Cursor::new(bytes))
+            }
+        };
+
+        response.ok()
+    }
+}
+
+async fn read_file(path: &Path) -> Option<StaticFileBody> {
+    // opening a directory works, but reading it doesn't
+    if !fs::metadata(path).await.ok()?.is_file() {
+        return None;
+    }
+
+    fs::File::open(path).await.ok().map(StaticFileBody::File)
+}
+
+fn content_type(path: &str) -> ContentType {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| ContentType::from_extension(&extension.to_string_lossy()))
+        .unwrap_or(ContentType::Binary)
+}
+
+fn cache_control(path: &str) -> &'static str {
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    if filename == "index.html" {
+        CACHE_NEVER
+    } else if is_hashed(filename) {
+        CACHE_FOREVER
+    } else {
+        CACHE_BRIEFLY
+    }
+}
+
+/// Whether a filename has a content hash in it, the way the React build
+/// names its scripts and stylesheets, such as `main.8e2a1c3f.chunk.js`.
+fn is_hashed(filename: &str) -> bool {
+    let parts = filename.split('.').collect::<Vec<_>>();
+
+    parts.len() > 2
+        && parts[1..parts.len() - 1]
+            .iter()
+            .any(|part| part.len() >= 8 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The tags in the app's index.html which load the app.
+fn find_app_tags(index_html: &str) -> String {
+    let document = Html::parse_document(index_html);
+    let selector = Selector::parse(r#"script, link[rel="stylesheet"]"#).unwrap();
+
+    document
+        .select(&selector)
+        .map(|element| element.html())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_control() {
+        assert_eq!(
+            CACHE_FOREVER,
+            cache_control("static/js/main.8e2a1c3f.chunk.js")
+        );
+        assert_eq!(
+            CACHE_FOREVER,
+            cache_control("static/css/2.0a1b2c3d.chunk.css")
+        );
+        assert_eq!(CACHE_BRIEFLY, cache_control("icons/github.png"));
+        assert_eq!(CACHE_BRIEFLY, cache_control("manifest.json"));
+        assert_eq!(CACHE_NEVER, cache_control("index.html"));
+    }
+
+    #[test]
+    fn test_find_app_tags() {
+        let index_html = r#"<!doctype html><html><head>
+            <link rel="icon" href="/favicon.ico">
+            <link href="/static/css/main.0a1b2c3d.chunk.css" rel="stylesheet">
+        </head><body>
+            <div id="root"></div>
+            <script>!function(e){window.x=e<1}(0)</script>
+            <script src="/static/js/main.8e2a1c3f.chunk.js"></script>
+        </body></html>"#;
+
+        let app_tags = find_app_tags(index_html);
+        let lines = app_tags.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("<link "));
+        assert!(lines[0].contains(r#"href="/static/css/main.0a1b2c3d.chunk.css""#));
+        assert_eq!("<script>!function(e){window.x=e<1}(0)</script>", lines[1]);
+        assert_eq!(
+            r#"<script src="/static/js/main.8e2a1c3f.chunk.js"></script>"#,
+            lines[2]
+        );
+    }
+}
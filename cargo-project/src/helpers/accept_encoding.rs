@@ -0,0 +1,77 @@
+use rocket::{
+    request::{FromRequest, Outcome},
+    Request,
+};
+use std::convert::Infallible;
+
+/// Which of the compressed encodings we keep files in the client will take,
+/// going by its `Accept-Encoding`.
+#[derive(Debug, Default, PartialEq)]
+pub struct AcceptEncoding {
+    pub brotli: bool,
+    pub gzip: bool,
+}
+
+impl AcceptEncoding {
+    pub fn parse(header: &str) -> Self {
+        let mut accepted = Self::default();
+        for coding in header.split(',') {
+            let mut params = coding.split(';').map(str::trim);
+            let name = params.next().unwrap_or("").to_lowercase();
+            // a weight of zero means anything but this
+            let refused = params.any(|param| {
+                param.strip_prefix("q=").and_then(|q| q.parse::<f32>().ok()) == Some(0.0)
+            });
+            if refused {
+                continue;
+            }
+
+            match name.as_str() {
+                "br" => accepted.brotli = true,
+                "gzip" => accepted.gzip = true,
+                _ => {}
+            }
+        }
+
+        accepted
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptEncoding {
+    type Error = Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let header = req
+            .headers()
+            .get("Accept-Encoding")
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Outcome::Success(Self::parse(&header))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            AcceptEncoding {
+                brotli: true,
+                gzip: true
+            },
+            AcceptEncoding::parse("gzip, deflate, br")
+        );
+        assert_eq!(
+            AcceptEncoding {
+                brotli: false,
+                gzip: true
+            },
+            AcceptEncoding::parse("br;q=0, GZIP;q=0.5")
+        );
+        assert_eq!(AcceptEncoding::default(), AcceptEncoding::parse(""));
+    }
+}
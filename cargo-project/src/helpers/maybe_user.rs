@@ -18,6 +18,11 @@ impl MaybeUser {
     pub fn is_admin(&self) -> bool {
         self.permissions.contains(&"admin".into())
     }
+
+    /// The id of the logged in user, if anyone is.
+    pub fn user_id(&self) -> Option<i32> {
+        self.user.as_ref().map(|(user, _)| user.id)
+    }
 }
 
 #[rocket::async_trait]
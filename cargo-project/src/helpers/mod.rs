@@ -1,3 +1,4 @@
+pub mod accept_encoding;
 pub mod admin_only;
 pub mod maybe_user;
 
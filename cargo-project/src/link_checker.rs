@@ -0,0 +1,274 @@
+//! Checks that the links snippets point to still go somewhere. Links on a
+//! site this old rot quietly, so every snippet's href is asked for now and
+//! then and the answer is kept in `link_checks` for admins to go over.
+
+use crate::{
+    application_context::ApplicationContext,
+    models::{LinkCheck, ModelError, Snippet},
+};
+use chrono::Utc;
+use log::info;
+use reqwest::{header::LOCATION, redirect::Policy, Client as ReqwestClient};
+use std::time::Duration;
+
+/// How long to wait on any one link before giving up on it.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What came back from asking for a link.
+#[derive(Debug, PartialEq)]
+pub struct LinkStatus {
+    /// The HTTP status code, if anything answered.
+    pub status_code: Option<u16>,
+
+    /// Where a redirect pointed, made absolute.
+    pub redirect_to: Option<String>,
+
+    /// Why nothing answered.
+    pub error: Option<String>,
+}
+
+/// How a run over every snippet went.
+#[derive(Debug, Default)]
+pub struct LinkCheckSummary {
+    /// How many links were checked.
+    pub checked: usize,
+
+    /// How many of those were dead.
+    pub dead: usize,
+
+    /// How many snippets were hidden for having been dead too long.
+    pub hidden: usize,
+}
+
+/// An HTTP client set up for checking links. Redirects are not followed so
+/// that where they lead can be recorded.
+#[derive(Clone)]
+pub struct LinkChecker {
+    http_client: ReqwestClient,
+}
+
+impl LinkChecker {
+    pub fn new() -> Self {
+        Self {
+            http_client: reqwest::ClientBuilder::new()
+                .user_agent("Rust/reqwest/iDevGames.com")
+                .redirect(Policy::none())
+                .timeout(TIMEOUT)
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// Asks for a link with a HEAD request, falling back on a GET if that
+    /// comes back as an error, since plenty of servers mishandle HEAD.
+    pub async fn check(&self, href: &str) -> LinkStatus {
+        let response = match self.http_client.head(href).send().await {
+            Ok(r) if r.status().is_client_error() || r.status().is_server_error() => {
+                self.http_client.get(href).send().await
+            }
+            r => r,
+        };
+
+        match response {
+            Ok(r) => {
+                let redirect_to = if r.status().is_redirection() {
+                    r.headers()
+                        .get(LOCATION)
+                        .and_then(|location| location.to_str().ok())
+                        .map(|location| match r.url().join(location) {
+                            Ok(url) => url.to_string(),
+                            Err(_) => location.to_owned(),
+                        })
+                } else {
+                    None
+                };
+
+                LinkStatus {
+                    status_code: Some(r.status().as_u16()),
+                    redirect_to,
+                    error: None,
+                }
+            }
+            Err(e) => LinkStatus {
+                status_code: None,
+                redirect_to: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Checks the href of every snippet not in the trash, one at a time, and
+/// records how each went. When `auto_hide_after` is given, visible snippets
+/// whose links have been dead for that many checks in a row are hidden.
+pub async fn check_snippets(
+    ctxt: &ApplicationContext,
+    auto_hide_after: Option<i32>,
+) -> Result<LinkCheckSummary, ModelError> {
+    let hrefs = Snippet::find_all_hrefs(&ctxt.db_pool.read().get()?)?;
+    let mut summary = LinkCheckSummary::default();
+
+    for (snippet_id, href) in hrefs {
+        let status = ctxt.link_checker.check(&href).await;
+
+        // don't hang on to a connection while waiting on the network
+        let conn = ctxt.db_pool.read().get()?;
+        // the snippet may have gone in the trash while the others were
+        // being checked, which is no reason to stop checking the rest
+        let mut snippet = match Snippet::find_by_id(&conn, snippet_id) {
+            Ok(snippet) => snippet,
+            Err(e) if e.is_not_found() => continue,
+            Err(e) => return Err(e),
+        };
+        let link_check = LinkCheck::record(
+            &conn,
+            snippet_id,
+            status.status_code.map(i32::from),
+            status.redirect_to.as_deref(),
+            status.error.as_deref(),
+            &Utc::now().naive_utc(),
+        )?;
+
+        summary.checked += 1;
+        if link_check.consecutive_failures == 0 {
+            continue;
+        }
+        summary.dead += 1;
+
+        if let Some(n) = auto_hide_after {
+            if link_check.consecutive_failures >= n && !snippet.hidden {
+                info!("Hiding snippet {} since {} is dead", snippet_id, href);
+                snippet.hidden = true;
+                snippet.update(&conn, None)?;
+                summary.hidden += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_pool, github_client::GithubClient, models::snippets::create_test_snippet,
+        page_metadata::PageMetadataClient,
+    };
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    /// A tiny HTTP server on a random port which answers a few fixed paths,
+    /// and doesn't do HEAD on one of them. Returns the base URL.
+    fn stub_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                reader.read_line(&mut request_line).unwrap();
+                // drain the headers so the client isn't cut off mid-request
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+
+                let mut parts = request_line.split_whitespace();
+                let method = parts.next().unwrap_or("");
+                let path = parts.next().unwrap_or("");
+                let response = match (method, path) {
+                    (_, "/ok") => "HTTP/1.1 200 OK\r\n",
+                    (_, "/moved") => "HTTP/1.1 301 Moved Permanently\r\nLocation: /ok\r\n",
+                    ("HEAD", "/no-head") => "HTTP/1.1 405 Method Not Allowed\r\n",
+                    ("GET", "/no-head") => "HTTP/1.1 200 OK\r\n",
+                    _ => "HTTP/1.1 404 Not Found\r\n",
+                };
+                let _ = write!(
+                    stream,
+                    "{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+                    response
+                );
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[rocket::async_test]
+    async fn test_check() {
+        let base = stub_server();
+        let checker = LinkChecker::new();
+
+        assert_eq!(
+            Some(200),
+            checker.check(&format!("{}/ok", base)).await.status_code
+        );
+        assert_eq!(
+            Some(404),
+            checker.check(&format!("{}/gone", base)).await.status_code
+        );
+        assert_eq!(
+            Some(200),
+            checker
+                .check(&format!("{}/no-head", base))
+                .await
+                .status_code
+        );
+        assert_eq!(
+            LinkStatus {
+                status_code: Some(301),
+                redirect_to: Some(format!("{}/ok", base)),
+                error: None,
+            },
+            checker.check(&format!("{}/moved", base)).await
+        );
+
+        // nothing listens on port 1
+        let status = checker.check("http://127.0.0.1:1/").await;
+        assert_eq!(None, status.status_code);
+        assert!(status.error.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn test_check_snippets() {
+        let base = stub_server();
+        let ctxt = ApplicationContext {
+            github_client: GithubClient::new("", ""),
+            link_checker: LinkChecker::new(),
+            page_metadata_client: PageMetadataClient::new(),
+            db_pool: test_pool(),
+            site_url: "".to_owned(),
+            icon_dir: "".into(),
+            frontend: None,
+        };
+
+        let (alive, dead) = {
+            let conn = ctxt.db_pool.read().get().unwrap();
+            let share = |href: String| create_test_snippet(&conn, &href);
+            (
+                share(format!("{}/ok", base)),
+                share(format!("{}/gone", base)),
+            )
+        };
+
+        let summary = check_snippets(&ctxt, Some(2)).await.unwrap();
+        assert_eq!((2, 1, 0), (summary.checked, summary.dead, summary.hidden));
+
+        let summary = check_snippets(&ctxt, Some(2)).await.unwrap();
+        assert_eq!((2, 1, 1), (summary.checked, summary.dead, summary.hidden));
+
+        let conn = ctxt.db_pool.read().get().unwrap();
+        assert!(!Snippet::find_by_id(&conn, alive.id).unwrap().hidden);
+        assert!(Snippet::find_by_id(&conn, dead.id).unwrap().hidden);
+        let report = LinkCheck::find_report(&conn, true).unwrap();
+        assert_eq!(1, report.len());
+        assert_eq!(Some(404), report[0].1.status_code);
+        assert_eq!(2, report[0].1.consecutive_failures);
+    }
+}
@@ -9,10 +9,17 @@ mod application_context;
 mod cli;
 mod controllers;
 mod db;
+mod discord_import;
+mod favicons;
+mod frontend;
 mod github_client;
 mod helpers;
+mod link_checker;
+mod markdown;
 mod models;
+mod page_metadata;
 mod schema;
+mod snippet_transfer;
 
 use application_context::ApplicationContext;
 use clap::Clap;
@@ -20,6 +27,8 @@ use cli::Opts;
 use db::get_pool;
 use dotenv::dotenv;
 use github_client::GithubClient;
+use link_checker::LinkChecker;
+use page_metadata::PageMetadataClient;
 use std::{any::type_name, env, str::FromStr};
 
 #[rocket::main]
@@ -33,9 +42,20 @@ async fn main() {
     // purpose.
     let db_pool = get_pool(&env_str("DATABASE_URL"), env_parse::<u32>("IDG_MAXDBCONNS"));
     let github_client = GithubClient::new(&env_str("GH_CLIENT_ID"), &env_str("GH_CLIENT_SECRET"));
+    let site_url = env::var("IDG_SITE_URL")
+        .unwrap_or_else(|_| "https://www.idevgames.com".to_owned())
+        .trim_end_matches('/')
+        .to_owned();
+    let icon_dir = env::var("IDG_ICON_DIR").unwrap_or_else(|_| "icons".to_owned());
     let application_context = ApplicationContext {
         db_pool,
         github_client,
+        link_checker: LinkChecker::new(),
+        page_metadata_client: PageMetadataClient::new(),
+        site_url,
+        icon_dir: icon_dir.into(),
+        // only serving needs the app's build, so that's where it's loaded
+        frontend: None,
     };
 
     let opts = Opts::parse();
@@ -0,0 +1,92 @@
+//! Renders the Markdown that admins write in snippets to HTML that is safe to
+//! put straight onto a page.
+//!
+//! Markdown happily passes raw HTML through, so everything pulldown-cmark
+//! produces is run through an allow-list sanitizer afterwards. Anything not on
+//! the list, such as `<script>`, inline event handlers, or `javascript:` links,
+//! is stripped.
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use std::collections::HashSet;
+
+/// Renders Markdown to sanitized HTML.
+pub fn render(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+
+    sanitize(&unsafe_html)
+}
+
+/// Renders Markdown to plain text, for places which can't show any
+/// formatting at all, such as the description of a page in a link preview.
+pub fn render_plain(markdown: &str) -> String {
+    let text = Parser::new(markdown)
+        .filter_map(|event| match event {
+            Event::Text(text) | Event::Code(text) => Some(text.into_string()),
+            Event::End(
+                Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..),
+            ) => None,
+            // the end of anything else, such as a paragraph, is a break
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => Some(" ".to_owned()),
+            _ => None,
+        })
+        .collect::<String>();
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Strips everything that isn't explicitly allowed out of some HTML.
+fn sanitize(unsafe_html: &str) -> String {
+    // ammonia's default tag and attribute lists are already an allow-list of
+    // formatting-only markup. The URL schemes are tightened up a bit, and
+    // links are marked so that search engines don't think we're vouching
+    // for anything we link to.
+    Builder::default()
+        .url_schemes(
+            ["http", "https", "mailto"]
+                .iter()
+                .cloned()
+                .collect::<HashSet<_>>(),
+        )
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .clean(unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, render_plain};
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            "<p>Some <strong>bold</strong> text</p>\n",
+            render("Some **bold** text")
+        );
+        assert_eq!(
+            "<p><a href=\"https://example.com\" rel=\"noopener noreferrer nofollow\">ok</a> </p>\n",
+            render("[ok](https://example.com) <script>alert(1)</script>")
+        );
+        assert_eq!(
+            "<p><a rel=\"noopener noreferrer nofollow\">no</a></p>\n",
+            render("[no](javascript:alert(1))")
+        );
+        assert_eq!("<img src=\"x\">", render("<img src=x onerror=alert(1)>"));
+    }
+
+    #[test]
+    fn test_render_plain() {
+        assert_eq!(
+            "Some bold text and a link. Another paragraph",
+            render_plain(
+                "Some **bold**\ntext and [a link](https://example.com).\n\nAnother paragraph"
+            )
+        );
+        assert_eq!("", render_plain("<script>alert(1)</script>"));
+    }
+}
@@ -0,0 +1,119 @@
+use super::ModelError;
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A snippet a user saved for later. Users bookmark a snippet at most once.
+/// To list a user's bookmarks, filter snippets by `bookmarked_by`.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+    pub id: i32,
+    pub snippet_id: i32,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl Bookmark {
+    /// Bookmarks the snippet for the user, unless they already have.
+    pub fn create(conn: &DbConn, the_snippet_id: i32, the_user_id: i32) -> Result<(), ModelError> {
+        use crate::schema::bookmarks::dsl::{bookmarks, created_at, snippet_id, user_id};
+        use diesel::prelude::*;
+
+        diesel::insert_or_ignore_into(bookmarks)
+            .values((
+                snippet_id.eq(the_snippet_id),
+                user_id.eq(the_user_id),
+                created_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Removes the user's bookmark of the snippet, if they had one.
+    pub fn delete(conn: &DbConn, the_snippet_id: i32, the_user_id: i32) -> Result<(), ModelError> {
+        use crate::schema::bookmarks::dsl::{bookmarks, snippet_id, user_id};
+        use diesel::prelude::*;
+
+        diesel::delete(
+            bookmarks
+                .filter(snippet_id.eq(the_snippet_id))
+                .filter(user_id.eq(the_user_id)),
+        )
+        .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Which of the given snippets the user has bookmarked.
+    pub fn find_bookmarked_snippet_ids(
+        conn: &DbConn,
+        the_user_id: i32,
+        the_snippet_ids: &[i32],
+    ) -> Result<HashSet<i32>, ModelError> {
+        use crate::schema::bookmarks::dsl::{bookmarks, snippet_id, user_id};
+        use diesel::prelude::*;
+
+        let r = bookmarks
+            .select(snippet_id)
+            .filter(user_id.eq(the_user_id))
+            .filter(snippet_id.eq_any(the_snippet_ids))
+            .load::<i32>(conn)?;
+
+        Ok(r.into_iter().collect())
+    }
+
+    /// Forgets every bookmark of a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::bookmarks::dsl::{bookmarks, snippet_id};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(bookmarks.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_pool,
+        models::{snippets::create_test_snippet, Snippet, SnippetFilter, SnippetSort},
+    };
+
+    #[test]
+    fn test_bookmarks() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let first = create_test_snippet(&conn, "https://example.com/a").id;
+        let second = create_test_snippet(&conn, "https://example.com/b").id;
+
+        Bookmark::create(&conn, first, 2).unwrap();
+        Bookmark::create(&conn, first, 2).unwrap();
+        Bookmark::create(&conn, second, 2).unwrap();
+        Bookmark::create(&conn, second, 3).unwrap();
+        Bookmark::delete(&conn, second, 2).unwrap();
+
+        let filter = SnippetFilter {
+            bookmarked_by: Some(2),
+            ..SnippetFilter::default()
+        };
+        assert_eq!(1, Snippet::count(&conn, &filter).unwrap());
+        assert_eq!(
+            vec![first],
+            Snippet::find_filtered(&conn, &filter, SnippetSort::SharedOn, 0, 10)
+                .unwrap()
+                .iter()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![second].into_iter().collect::<HashSet<_>>(),
+            Bookmark::find_bookmarked_snippet_ids(&conn, 3, &[first, second]).unwrap()
+        );
+    }
+}
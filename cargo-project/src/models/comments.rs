@@ -0,0 +1,167 @@
+use super::{last_insert_rowid, ModelError};
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+
+/// Something a user had to say about a snippet, in Markdown. Comments with a
+/// parent are replies, so a snippet's comments make up a set of threads.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: i32,
+    pub snippet_id: i32,
+
+    /// The comment this replies to, on the same snippet.
+    pub parent_id: Option<i32>,
+
+    /// The user who wrote this.
+    pub author_id: i32,
+    pub body: String,
+
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+
+    /// When this was deleted, by its author or an admin. A deleted comment
+    /// keeps its place so its replies still have somewhere to hang, but its
+    /// body is gone for good.
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+impl Comment {
+    pub fn create(
+        conn: &DbConn,
+        the_snippet_id: i32,
+        the_parent_id: Option<i32>,
+        the_author_id: i32,
+        the_body: &str,
+    ) -> Result<Self, ModelError> {
+        use crate::schema::comments::dsl::{
+            author_id, body, comments, created_at, parent_id, snippet_id, updated_at,
+        };
+        use diesel::prelude::*;
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            let now = Utc::now().naive_utc();
+
+            diesel::insert_into(comments)
+                .values((
+                    snippet_id.eq(the_snippet_id),
+                    parent_id.eq(the_parent_id),
+                    author_id.eq(the_author_id),
+                    body.eq(the_body),
+                    created_at.eq(&now),
+                    updated_at.eq(&now),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Self::find_by_id(conn, rowid)
+        })
+    }
+
+    pub fn find_by_id(conn: &DbConn, the_id: i32) -> Result<Self, ModelError> {
+        use crate::schema::comments::dsl::comments;
+        use diesel::prelude::*;
+
+        Ok(comments.find(the_id).first::<Self>(conn)?)
+    }
+
+    /// Every comment on a snippet, deleted ones included, oldest first. A
+    /// reply always comes after what it replies to.
+    pub fn find_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::comments::dsl::{comments, created_at, id, snippet_id};
+        use diesel::prelude::*;
+
+        let r = comments
+            .filter(snippet_id.eq(the_snippet_id))
+            .order((created_at.asc(), id.asc()))
+            .load::<Self>(conn)?;
+
+        Ok(r)
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// Replaces the body with an edited one.
+    pub fn edit(&mut self, conn: &DbConn, the_body: &str) -> Result<(), ModelError> {
+        use crate::schema::comments::dsl::{body, comments, updated_at};
+        use diesel::prelude::*;
+
+        let now = Utc::now().naive_utc();
+        diesel::update(comments.find(self.id))
+            .set((body.eq(the_body), updated_at.eq(&now)))
+            .execute(conn)?;
+
+        self.body = the_body.to_owned();
+        self.updated_at = now;
+
+        Ok(())
+    }
+
+    /// Deletes the comment, throwing its body away but leaving it in place
+    /// for the sake of any replies.
+    pub fn delete(&mut self, conn: &DbConn) -> Result<(), ModelError> {
+        use crate::schema::comments::dsl::{body, comments, deleted_at};
+        use diesel::prelude::*;
+
+        let now = Utc::now().naive_utc();
+        diesel::update(comments.find(self.id))
+            .set((body.eq(""), deleted_at.eq(&now)))
+            .execute(conn)?;
+
+        self.body = "".to_owned();
+        self.deleted_at = Some(now);
+
+        Ok(())
+    }
+
+    /// Forgets the comments on a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::comments::dsl::{comments, snippet_id};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(comments.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[test]
+    fn test_thread() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let mut first = Comment::create(&conn, 1, None, 1, "First").unwrap();
+        let reply = Comment::create(&conn, 1, Some(first.id), 2, "Reply").unwrap();
+        Comment::create(&conn, 2, None, 1, "Elsewhere").unwrap();
+
+        first.edit(&conn, "First, edited").unwrap();
+        assert_eq!(
+            vec![
+                (first.id, None, "First, edited"),
+                (reply.id, Some(first.id), "Reply")
+            ],
+            Comment::find_by_snippet_id(&conn, 1)
+                .unwrap()
+                .iter()
+                .map(|c| (c.id, c.parent_id, c.body.as_str()))
+                .collect::<Vec<_>>()
+        );
+
+        first.delete(&conn).unwrap();
+        let first = Comment::find_by_id(&conn, first.id).unwrap();
+        assert!(first.is_deleted());
+        assert_eq!("", first.body);
+
+        assert_eq!(2, Comment::delete_by_snippet_id(&conn, 1).unwrap());
+        assert_eq!(1, Comment::find_by_snippet_id(&conn, 2).unwrap().len());
+    }
+}
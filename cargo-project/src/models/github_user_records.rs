@@ -4,6 +4,7 @@ use crate::{
 };
 
 use super::users::User;
+use std::collections::HashMap;
 
 /// Local cache of part of Github's understanding of who a user is. Particularly
 /// the id, which persists across use renames, and the user's login, which is a
@@ -114,6 +115,24 @@ impl GithubUserRecord {
         r_to_opt(user_record)
     }
 
+    /// Finds the GithubUserRecords of several users at once, by their
+    /// iDevGames-side user ids.
+    pub fn find_by_user_ids(
+        conn: &DbConn,
+        the_user_ids: &[i32],
+    ) -> Result<HashMap<i32, Self>, ModelError> {
+        use crate::schema::github_user_records::dsl::{github_user_records, user_id};
+        use diesel::prelude::*;
+
+        let r = github_user_records
+            .filter(user_id.eq_any(the_user_ids))
+            .load::<Self>(conn)?;
+
+        Ok(r.into_iter()
+            .map(|record| (record.user_id, record))
+            .collect())
+    }
+
     /// Gets the User this GithubUserRecord corresponds to.
     pub fn get_user(&self, conn: &DbConn) -> Result<User, ModelError> {
         let u = User::find_by_id(&conn, self.user_id).transpose();
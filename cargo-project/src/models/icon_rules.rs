@@ -0,0 +1,180 @@
+use super::{last_insert_rowid, r_to_opt, ModelError};
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use reqwest::Url;
+use serde::Serialize;
+
+/// Picks the icon for links to a site. The pattern is either a host, such as
+/// `store.steampowered.com`, which matches that host with or without `www.`,
+/// or a wildcard such as `*.itch.io`, which matches `itch.io` and any of its
+/// subdomains. When several rules match, one for the exact host wins over a
+/// wildcard, and otherwise the longest pattern wins.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconRule {
+    pub id: i32,
+    pub pattern: String,
+
+    /// The filename of an icon in the registry.
+    pub icon: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl IconRule {
+    pub fn create(conn: &DbConn, the_pattern: &str, the_icon: &str) -> Result<Self, ModelError> {
+        use crate::schema::icon_rules::dsl::{created_at, icon, icon_rules, id, pattern};
+        use diesel::prelude::*;
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            diesel::insert_into(icon_rules)
+                .values((
+                    pattern.eq(the_pattern.to_lowercase()),
+                    icon.eq(the_icon),
+                    created_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Ok(icon_rules.filter(id.eq(rowid)).first::<Self>(conn)?)
+        })
+    }
+
+    pub fn find_by_id(conn: &DbConn, the_id: i32) -> Result<Option<Self>, ModelError> {
+        use crate::schema::icon_rules::dsl::icon_rules;
+        use diesel::prelude::*;
+
+        r_to_opt(icon_rules.find(the_id).first::<Self>(conn))
+    }
+
+    pub fn find_by_pattern(conn: &DbConn, the_pattern: &str) -> Result<Option<Self>, ModelError> {
+        use crate::schema::icon_rules::dsl::{icon_rules, pattern};
+        use diesel::prelude::*;
+
+        r_to_opt(
+            icon_rules
+                .filter(pattern.eq(the_pattern))
+                .first::<Self>(conn),
+        )
+    }
+
+    /// Every rule, by pattern.
+    pub fn find_all(conn: &DbConn) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::icon_rules::dsl::{icon_rules, pattern};
+        use diesel::prelude::*;
+
+        Ok(icon_rules.order(pattern.asc()).load::<Self>(conn)?)
+    }
+
+    pub fn delete(&self, conn: &DbConn) -> Result<usize, ModelError> {
+        use crate::schema::icon_rules::dsl::icon_rules;
+        use diesel::prelude::*;
+
+        Ok(diesel::delete(icon_rules.find(self.id)).execute(conn)?)
+    }
+
+    /// The icon the rules pick for a link, if any of them match its host.
+    pub fn suggest_icon(conn: &DbConn, href: &str) -> Result<Option<String>, ModelError> {
+        Ok(Self::pick(&Self::find_all(conn)?, href).map(str::to_owned))
+    }
+
+    /// The icon the best of the rules picks for a link. For going over lots
+    /// of links without loading the rules for each one.
+    pub fn pick<'a>(rules: &'a [Self], href: &str) -> Option<&'a str> {
+        let url = Url::parse(href).ok()?;
+        let host = url.host_str()?.to_lowercase();
+
+        rules
+            .iter()
+            .filter_map(|rule| rule.score(&host).map(|score| (score, rule)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, rule)| rule.icon.as_str())
+    }
+
+    /// Whether a pattern is a host, or `*.` and a domain, as rules expect.
+    pub fn is_valid_pattern(the_pattern: &str) -> bool {
+        let domain = the_pattern.strip_prefix("*.").unwrap_or(the_pattern);
+
+        domain.contains('.')
+            && domain.split('.').all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            })
+    }
+
+    /// How well the rule matches a host, if it does at all. Bigger is better.
+    fn score(&self, host: &str) -> Option<usize> {
+        match self.pattern.strip_prefix("*.") {
+            Some(domain) => {
+                if host == domain || host.ends_with(&format!(".{}", domain)) {
+                    Some(domain.len())
+                } else {
+                    None
+                }
+            }
+            None => {
+                if host == self.pattern || host.strip_prefix("www.") == Some(&self.pattern) {
+                    // an exact host beats any wildcard, however long
+                    Some(usize::MAX)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[test]
+    fn test_suggest_icon() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let suggest = |href| IconRule::suggest_icon(&conn, href).unwrap();
+        assert_eq!(
+            Some("itchio.png".to_owned()),
+            suggest("https://someone.itch.io/game")
+        );
+        assert_eq!(Some("itchio.png".to_owned()), suggest("https://itch.io/"));
+        assert_eq!(None, suggest("https://notitch.io/"));
+        assert_eq!(None, suggest("not a url"));
+
+        IconRule::create(&conn, "*.Docs.GitHub.com", "safari.png").unwrap();
+        IconRule::create(&conn, "gist.github.com", "ferris-happy.svg").unwrap();
+        assert_eq!(
+            Some("github.png".to_owned()),
+            suggest("https://github.com/a/b")
+        );
+        assert_eq!(
+            Some("safari.png".to_owned()),
+            suggest("https://en.docs.github.com/")
+        );
+        assert_eq!(
+            Some("ferris-happy.svg".to_owned()),
+            suggest("https://www.gist.github.com/")
+        );
+        assert_eq!(
+            Some("github.png".to_owned()),
+            suggest("https://a.gist.github.com/")
+        );
+    }
+
+    #[test]
+    fn test_is_valid_pattern() {
+        assert!(IconRule::is_valid_pattern("example.com"));
+        assert!(IconRule::is_valid_pattern("*.example.co.uk"));
+        assert!(!IconRule::is_valid_pattern("*"));
+        assert!(!IconRule::is_valid_pattern("*.com"));
+        assert!(!IconRule::is_valid_pattern("ex*mple.com"));
+        assert!(!IconRule::is_valid_pattern("https://example.com"));
+        assert!(!IconRule::is_valid_pattern("example..com"));
+    }
+}
@@ -0,0 +1,397 @@
+use super::{last_insert_rowid, r_to_opt, ModelError};
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use scraper::Html;
+use serde::Serialize;
+
+/// An image a snippet can use as its icon. Snippets name their icon by its
+/// filename, which is also where the frontend finds it, under `/icons/`.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Icon {
+    pub id: i32,
+
+    /// What people call the icon, such as "YouTube".
+    pub name: String,
+    pub filename: String,
+    pub content_type: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Icon {
+    pub fn create(
+        conn: &DbConn,
+        the_name: &str,
+        the_filename: &str,
+        the_content_type: &str,
+    ) -> Result<Self, ModelError> {
+        use crate::schema::icons::dsl::{content_type, created_at, filename, icons, id, name};
+        use diesel::prelude::*;
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            diesel::insert_into(icons)
+                .values((
+                    name.eq(the_name),
+                    filename.eq(the_filename),
+                    content_type.eq(the_content_type),
+                    created_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Ok(icons.filter(id.eq(rowid)).first::<Self>(conn)?)
+        })
+    }
+
+    /// Every icon, by name.
+    pub fn find_all(conn: &DbConn) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::icons::dsl::{icons, name};
+        use diesel::prelude::*;
+
+        Ok(icons.order(name.asc()).load::<Self>(conn)?)
+    }
+
+    pub fn find_by_filename(conn: &DbConn, the_filename: &str) -> Result<Option<Self>, ModelError> {
+        use crate::schema::icons::dsl::{filename, icons};
+        use diesel::prelude::*;
+
+        r_to_opt(icons.filter(filename.eq(the_filename)).first::<Self>(conn))
+    }
+
+    pub fn find_by_name(conn: &DbConn, the_name: &str) -> Result<Option<Self>, ModelError> {
+        use crate::schema::icons::dsl::{icons, name};
+        use diesel::prelude::*;
+
+        r_to_opt(icons.filter(name.eq(the_name)).first::<Self>(conn))
+    }
+
+    /// Filenames end up in URLs and on disk, so they're kept plain.
+    pub fn is_valid_filename(the_filename: &str) -> bool {
+        !the_filename.is_empty()
+            && the_filename.len() <= 64
+            && !the_filename.starts_with('.')
+            && the_filename
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c))
+    }
+
+    /// Whether a snippet could use the icon with this filename.
+    pub fn is_registered(conn: &DbConn, the_filename: &str) -> Result<bool, ModelError> {
+        Ok(Self::find_by_filename(conn, the_filename)?.is_some())
+    }
+}
+
+/// The kinds of image an icon can be.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IconKind {
+    Png,
+    Svg,
+    Ico,
+}
+
+impl IconKind {
+    /// The kind of icon a filename is for, going by its extension.
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let (_, extension) = filename.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "png" => Some(IconKind::Png),
+            "svg" => Some(IconKind::Svg),
+            "ico" => Some(IconKind::Ico),
+            _ => None,
+        }
+    }
+
+    /// The kind of icon some bytes are, if they're any kind we take.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        [IconKind::Png, IconKind::Ico, IconKind::Svg]
+            .iter()
+            .copied()
+            .find(|kind| kind.matches(bytes))
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            IconKind::Png => "png",
+            IconKind::Svg => "svg",
+            IconKind::Ico => "ico",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            IconKind::Png => "image/png",
+            IconKind::Svg => "image/svg+xml",
+            IconKind::Ico => "image/x-icon",
+        }
+    }
+
+    /// Whether the kind is a plain picture, which can't have anything in it
+    /// but pixels. Only these are taken from other sites.
+    pub fn is_raster(self) -> bool {
+        self != IconKind::Svg
+    }
+
+    /// Whether the bytes really are an image of this kind. SVGs are served
+    /// from our own domain, so one that could run script is turned away too.
+    pub fn matches(self, bytes: &[u8]) -> bool {
+        match self {
+            IconKind::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            IconKind::Ico => bytes.starts_with(&[0, 0, 1, 0]),
+            IconKind::Svg => match std::str::from_utf8(bytes) {
+                Ok(text) => is_inert_svg(text),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+/// The SVG elements an icon can have, which only ever draw something.
+const SVG_ELEMENTS: &[&str] = &[
+    "svg",
+    "g",
+    "defs",
+    "title",
+    "desc",
+    "path",
+    "rect",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "text",
+    "tspan",
+    "lineargradient",
+    "radialgradient",
+    "stop",
+    "clippath",
+    "mask",
+    "metadata",
+];
+
+/// The attributes those elements can have, none of which link anywhere or
+/// run anything.
+const SVG_ATTRIBUTES: &[&str] = &[
+    "id",
+    "class",
+    "style",
+    "version",
+    "width",
+    "height",
+    "viewbox",
+    "preserveaspectratio",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "fx",
+    "fy",
+    "d",
+    "points",
+    "transform",
+    "opacity",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "clip-path",
+    "clip-rule",
+    "clippathunits",
+    "mask",
+    "maskunits",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "gradientunits",
+    "gradienttransform",
+    "spreadmethod",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "text-anchor",
+    "dominant-baseline",
+];
+
+/// The namespaces drawing programs put their own notes about an SVG in,
+/// which browsers don't do anything with.
+const EDITOR_NAMESPACES: &[&str] = &[
+    "http://www.inkscape.org/namespaces/inkscape",
+    "http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd",
+    "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
+    "http://creativecommons.org/ns#",
+    "http://purl.org/dc/elements/1.1/",
+    "http://www.serif.com/",
+    "http://www.bohemiancoding.com/sketch/ns",
+];
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Whether some text is an SVG made only of elements and attributes which
+/// draw, so nothing in it can run script, link anywhere, or embed anything.
+/// Notes left by drawing programs are fine too, so long as they're in one
+/// of their own namespaces.
+fn is_inert_svg(text: &str) -> bool {
+    if !is_plain_xml(text) {
+        return false;
+    }
+
+    let document = Html::parse_fragment(text);
+    // the first element is the fragment's own <html>
+    let elements = document
+        .root_element()
+        .descendants()
+        .filter_map(|node| node.value().as_element())
+        .skip(1)
+        .collect::<Vec<_>>();
+
+    let svg = match elements.first() {
+        Some(svg) if &*svg.name.ns == SVG_NAMESPACE && &*svg.name.local == "svg" => svg,
+        _ => return false,
+    };
+
+    // the HTML parser leaves prefixed names as they are, so which namespace
+    // a prefix really is comes from the declarations on the <svg>
+    let editor_prefixes = svg
+        .attrs
+        .iter()
+        .filter(|(name, value)| name.ns.is_empty() && EDITOR_NAMESPACES.contains(&value.as_ref()))
+        .filter_map(|(name, _)| name.local.strip_prefix("xmlns:"))
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let is_editor_prefix = |prefix: &str| editor_prefixes.iter().any(|editor| editor == prefix);
+    let is_editor_name = |name: &str| match name.split_once(':') {
+        Some((prefix, _)) => is_editor_prefix(prefix),
+        None => false,
+    };
+
+    elements.iter().all(|element| {
+        let local = element.name.local.to_lowercase();
+
+        &*element.name.ns == SVG_NAMESPACE
+            && (SVG_ELEMENTS.contains(&local.as_str()) || is_editor_name(&local))
+            && element
+                .attrs
+                .iter()
+                .all(|(name, value)| match (&*name.ns, &*name.local) {
+                    ("", local) if local.starts_with("xmlns:") => {
+                        is_editor_prefix(&local["xmlns:".len()..])
+                            || (local == "xmlns:svg" && &**value == SVG_NAMESPACE)
+                    }
+                    ("", local) => {
+                        SVG_ATTRIBUTES.contains(&local.to_lowercase().as_str())
+                            || is_editor_name(local)
+                    }
+                    (XML_NAMESPACE, "space") | (XML_NAMESPACE, "lang") => true,
+                    (XMLNS_NAMESPACE, "xmlns") => &**value == SVG_NAMESPACE,
+                    (XMLNS_NAMESPACE, "xlink") => &**value == XLINK_NAMESPACE,
+                    _ => false,
+                })
+    })
+}
+
+/// Whether some XML has nothing in it that the HTML parser would miss, but
+/// a browser reading it as XML would act on: entities and the like declared
+/// in its doctype, or instructions such as a stylesheet to transform it with.
+fn is_plain_xml(text: &str) -> bool {
+    let lowercase = text.to_lowercase();
+    let has_internal_subset = lowercase.match_indices("<!doctype").any(|(i, _)| {
+        lowercase[i..]
+            .split('>')
+            .next()
+            .unwrap_or_default()
+            .contains('[')
+    });
+    let has_instructions = text
+        .match_indices("<?")
+        .any(|(i, _)| !text[i + 2..].starts_with("xml ") && !text[i + 2..].starts_with("xml?"));
+
+    !has_internal_subset && !has_instructions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[test]
+    fn test_icon_kind() {
+        assert_eq!(Some(IconKind::Svg), IconKind::from_filename("a.b.SVG"));
+        assert_eq!(None, IconKind::from_filename("gif"));
+
+        assert!(IconKind::Png.matches(b"\x89PNG\r\n\x1a\n..."));
+        assert!(!IconKind::Png.matches(b"GIF89a"));
+        assert_eq!(None, IconKind::from_filename("a.jpeg"));
+        assert!(IconKind::Ico.matches(&[0, 0, 1, 0, 1, 0]));
+        assert!(IconKind::Svg.matches(
+            br#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg"><path d="M0 0"/></svg>"#
+        ));
+        assert!(!IconKind::Svg.matches(br#"<svg><script>alert(1)</script></svg>"#));
+        assert!(!IconKind::Svg.matches(br#"<svg onload="alert(1)"></svg>"#));
+        assert!(!IconKind::Svg.matches(br#"<svg><a href=" javascript:alert(1)">x</a></svg>"#));
+        assert!(!IconKind::Svg.matches(br#"<svg><a href="jav&#9;ascript:alert(1)">x</a></svg>"#));
+        assert!(!IconKind::Svg.matches(
+            br#"<svg><animate attributeName="href" values="0;javascript:alert(1)"/></svg>"#
+        ));
+        assert!(!IconKind::Svg.matches(br#"<svg><use xlink:href="data:x"/></svg>"#));
+        assert!(!IconKind::Svg.matches(
+            br#"<svg xmlns:x="http://www.w3.org/2000/svg"><x:script>alert(1)</x:script></svg>"#
+        ));
+        assert!(!IconKind::Svg
+            .matches(br#"<!DOCTYPE svg [<!ENTITY x "<script>alert(1)</script>">]><svg>&x;</svg>"#));
+        assert!(!IconKind::Svg.matches(br#"<p>hi</p><svg></svg>"#));
+        assert!(!IconKind::Svg
+            .matches(br#"<?xml-stylesheet type="text/xsl" href="x.xsl"?><svg></svg>"#));
+        assert!(!IconKind::Svg.matches(
+            br#"<svg xmlns:svg="http://www.w3.org/2000/svg"><svg:script>alert(1)</svg:script></svg>"#
+        ));
+        assert!(!IconKind::Svg.matches(
+            br#"<svg xmlns:rdf="http://www.w3.org/1999/xhtml"><rdf:script>alert(1)</rdf:script></svg>"#
+        ));
+        assert!(IconKind::Svg.matches(
+            br#"<?xml version="1.0"?><svg xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xml:space="preserve"><metadata><rdf:RDF rdf:about=""/></metadata></svg>"#
+        ));
+        assert!(IconKind::Svg.matches(
+            br##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 8 8"><defs><linearGradient id="a"><stop offset="0" stop-color="#fff"/></linearGradient></defs><path fill="url(#a)" d="M0 0h8v8z"/></svg>"##
+        ));
+        assert!(!IconKind::Svg.matches(b"not an image"));
+        assert_eq!(Some(IconKind::Ico), IconKind::sniff(&[0, 0, 1, 0, 1, 0]));
+        assert_eq!(None, IconKind::sniff(b"GIF89a"));
+        assert_eq!(None, IconKind::sniff(b"BM"));
+        assert!(!IconKind::Svg.is_raster());
+        assert!(IconKind::Ico.is_raster());
+    }
+
+    #[test]
+    fn test_registry() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        assert!(Icon::is_registered(&conn, "youtube.svg").unwrap());
+        assert!(!Icon::is_registered(&conn, "new.png").unwrap());
+
+        Icon::create(&conn, "New", "new.png", "image/png").unwrap();
+        assert!(Icon::is_registered(&conn, "new.png").unwrap());
+        assert_eq!(
+            "new.png",
+            Icon::find_by_name(&conn, "New").unwrap().unwrap().filename
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use super::{last_insert_rowid, r_to_opt, ModelError, Snippet};
+use crate::db::DbConn;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// The outcome of the most recent check of a snippet's href. There is at most
+/// one of these per snippet; each check overwrites the last.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkCheck {
+    pub id: i32,
+    pub snippet_id: i32,
+
+    /// The HTTP status code the link answered with, if it answered at all.
+    pub status_code: Option<i32>,
+
+    /// Where the link redirected to, when it answered with a redirect.
+    pub redirect_to: Option<String>,
+
+    /// Why no answer came back, such as a timeout or a bad hostname.
+    pub error: Option<String>,
+
+    /// How many checks in a row have found this link dead. Zero when the
+    /// most recent check found it alive.
+    pub consecutive_failures: i32,
+
+    pub checked_at: NaiveDateTime,
+}
+
+impl LinkCheck {
+    /// Records the outcome of checking a snippet's href, keeping count of how
+    /// many times in a row it has been dead.
+    pub fn record(
+        conn: &DbConn,
+        the_snippet_id: i32,
+        the_status_code: Option<i32>,
+        the_redirect_to: Option<&str>,
+        the_error: Option<&str>,
+        the_checked_at: &NaiveDateTime,
+    ) -> Result<Self, ModelError> {
+        use crate::schema::link_checks::dsl::{
+            checked_at, consecutive_failures, error, id, link_checks, redirect_to, snippet_id,
+            status_code,
+        };
+        use diesel::prelude::*;
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            let previous = Self::find_by_snippet_id(conn, the_snippet_id)?;
+            let the_consecutive_failures = if is_dead(the_status_code, the_error) {
+                previous.as_ref().map_or(0, |p| p.consecutive_failures) + 1
+            } else {
+                0
+            };
+            let values = (
+                status_code.eq(the_status_code),
+                redirect_to.eq(the_redirect_to),
+                error.eq(the_error),
+                consecutive_failures.eq(the_consecutive_failures),
+                checked_at.eq(the_checked_at),
+            );
+
+            let rowid = match previous {
+                Some(previous) => {
+                    diesel::update(link_checks.find(previous.id))
+                        .set(values)
+                        .execute(conn)?;
+                    previous.id
+                }
+                None => {
+                    diesel::insert_into(link_checks)
+                        .values((snippet_id.eq(the_snippet_id), values))
+                        .execute(conn)?;
+                    diesel::select(last_insert_rowid).get_result::<i32>(conn)?
+                }
+            };
+
+            Ok(link_checks.filter(id.eq(rowid)).first::<Self>(conn)?)
+        })
+    }
+
+    /// Finds the most recent check of a snippet's href.
+    pub fn find_by_snippet_id(
+        conn: &DbConn,
+        the_snippet_id: i32,
+    ) -> Result<Option<Self>, ModelError> {
+        use crate::schema::link_checks::dsl::{link_checks, snippet_id};
+        use diesel::prelude::*;
+
+        let link_check = link_checks
+            .filter(snippet_id.eq(the_snippet_id))
+            .first::<Self>(conn);
+
+        r_to_opt(link_check)
+    }
+
+    /// Every checked snippet along with how its check went, the longest-dead
+    /// first. Snippets in the trash are left out.
+    pub fn find_report(conn: &DbConn, dead_only: bool) -> Result<Vec<(Snippet, Self)>, ModelError> {
+        use crate::schema::{link_checks, snippets};
+        use diesel::prelude::*;
+
+        let q = snippets::table
+            .inner_join(link_checks::table)
+            .filter(snippets::deleted_at.is_null())
+            .order((
+                link_checks::consecutive_failures.desc(),
+                link_checks::checked_at.desc(),
+            ));
+
+        let r = if dead_only {
+            q.filter(link_checks::consecutive_failures.gt(0))
+                .load::<(Snippet, Self)>(conn)?
+        } else {
+            q.load::<(Snippet, Self)>(conn)?
+        };
+
+        Ok(r)
+    }
+
+    /// Forgets the checks of a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::link_checks::dsl::{link_checks, snippet_id};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(link_checks.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+/// A link is dead when nothing answered, or when what answered was an error.
+/// Redirects count as alive; where they lead is recorded for a human to judge.
+fn is_dead(status_code: Option<i32>, error: Option<&str>) -> bool {
+    error.is_some() || !matches!(status_code, Some(code) if code < 400)
+}
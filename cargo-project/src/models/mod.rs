@@ -3,17 +3,40 @@
 //! intended to keep the database consistent. You should never manipulate the
 //! database directly from either command-line tool or controller code.
 
+pub(crate) mod bookmarks;
+pub(crate) mod comments;
 pub(crate) mod github_user_records;
+pub(crate) mod icon_rules;
+pub(crate) mod icons;
+pub(crate) mod link_checks;
 pub(crate) mod permissions;
+pub(crate) mod snippet_clicks;
+pub(crate) mod snippet_revisions;
+pub(crate) mod snippet_search;
+pub(crate) mod snippet_votes;
 pub(crate) mod snippets;
+pub(crate) mod submissions;
+pub(crate) mod tags;
+pub(crate) mod taxonomies;
 pub(crate) mod users;
 
 use diesel::{r2d2::PoolError, result::Error as DieselError};
 use thiserror::Error;
 
+pub use bookmarks::Bookmark;
+pub use comments::Comment;
 pub use github_user_records::GithubUserRecord;
+pub use icon_rules::IconRule;
+pub use icons::{Icon, IconKind};
+pub use link_checks::LinkCheck;
 pub use permissions::Permission;
-pub use snippets::Snippet;
+pub use snippet_clicks::{SnippetClick, SnippetClickTotal, TaxonomyClickTotal};
+pub use snippet_revisions::SnippetRevision;
+pub use snippet_search::SnippetSearchHit;
+pub use snippet_votes::SnippetVote;
+pub use snippets::{Snippet, SnippetCursor, SnippetFilter, SnippetSort, Visibility};
+pub use tags::{Tag, TagMatch};
+pub use taxonomies::Taxonomy;
 pub use users::User;
 
 /// An error common to model helper functions.
@@ -34,6 +57,14 @@ pub enum ModelError {
     NotFound,
 }
 
+impl ModelError {
+    /// Whether this error is a lookup by id that came back empty, as opposed
+    /// to something actually going wrong.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ModelError::DieselError(diesel::NotFound))
+    }
+}
+
 // Gets the most recently inserted row. Please only use this from within a
 // transaction to avoid threading adventures.
 no_arg_sql_function!(
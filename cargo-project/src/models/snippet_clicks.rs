@@ -0,0 +1,155 @@
+use super::ModelError;
+use crate::db::DbConn;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// How many times a snippet's link was followed on a given day. Clicks are
+/// only ever counted, never logged, so there's nothing in here about who
+/// clicked.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetClick {
+    pub snippet_id: i32,
+    pub day: NaiveDate,
+    pub clicks: i32,
+}
+
+/// The clicks a snippet got over some days.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetClickTotal {
+    pub snippet_id: i32,
+    pub title: String,
+    pub taxonomy: String,
+    pub clicks: i64,
+}
+
+/// The clicks all the snippets in a taxonomy got over some days.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxonomyClickTotal {
+    pub taxonomy: String,
+    pub clicks: i64,
+}
+
+impl SnippetClick {
+    /// Counts a click of the snippet's link on the given day.
+    pub fn record(
+        conn: &DbConn,
+        the_snippet_id: i32,
+        the_day: NaiveDate,
+    ) -> Result<(), ModelError> {
+        use crate::schema::snippet_clicks::dsl::{clicks, day, snippet_clicks, snippet_id};
+        use diesel::prelude::*;
+
+        conn.transaction::<(), ModelError, _>(|| {
+            diesel::insert_or_ignore_into(snippet_clicks)
+                .values((snippet_id.eq(the_snippet_id), day.eq(the_day), clicks.eq(0)))
+                .execute(conn)?;
+            diesel::update(snippet_clicks.find((the_snippet_id, the_day)))
+                .set(clicks.eq(clicks + 1))
+                .execute(conn)?;
+
+            Ok(())
+        })
+    }
+
+    /// Clicks per snippet from `since` to `until`, both inclusive, most
+    /// clicked first. Snippets nobody clicked are left out.
+    pub fn totals_by_snippet(
+        conn: &DbConn,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<SnippetClickTotal>, ModelError> {
+        use crate::schema::{snippet_clicks::dsl::*, snippets};
+        use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+        let rows = snippet_clicks
+            .inner_join(snippets::table)
+            .filter(day.ge(since))
+            .filter(day.le(until))
+            .group_by(snippet_id)
+            .select((
+                snippet_id,
+                snippets::title,
+                snippets::taxonomy,
+                sql::<BigInt>("SUM(clicks) AS total"),
+            ))
+            .order((sql::<BigInt>("total").desc(), snippet_id.asc()))
+            .load::<SnippetClickTotal>(conn)?;
+
+        Ok(rows)
+    }
+
+    /// Clicks per taxonomy from `since` to `until`, both inclusive, most
+    /// clicked first.
+    pub fn totals_by_taxonomy(
+        conn: &DbConn,
+        since: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<TaxonomyClickTotal>, ModelError> {
+        use crate::schema::{snippet_clicks::dsl::*, snippets};
+        use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+        let rows = snippet_clicks
+            .inner_join(snippets::table)
+            .filter(day.ge(since))
+            .filter(day.le(until))
+            .group_by(snippets::taxonomy)
+            .select((snippets::taxonomy, sql::<BigInt>("SUM(clicks) AS total")))
+            .order((sql::<BigInt>("total").desc(), snippets::taxonomy.asc()))
+            .load::<TaxonomyClickTotal>(conn)?;
+
+        Ok(rows)
+    }
+
+    /// Forgets the clicks of a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::snippet_clicks::dsl::{snippet_clicks, snippet_id};
+        use diesel::prelude::*;
+
+        let r =
+            diesel::delete(snippet_clicks.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
+
+    #[test]
+    fn test_totals() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let first = create_test_snippet(&conn, "https://example.com/a").id;
+        let second = create_test_snippet(&conn, "https://example.com/b").id;
+
+        let day = |d| NaiveDate::from_ymd_opt(2021, 1, d).unwrap();
+        SnippetClick::record(&conn, first, day(1)).unwrap();
+        SnippetClick::record(&conn, second, day(1)).unwrap();
+        SnippetClick::record(&conn, second, day(1)).unwrap();
+        SnippetClick::record(&conn, second, day(2)).unwrap();
+        SnippetClick::record(&conn, first, day(3)).unwrap();
+
+        assert_eq!(
+            vec![(second, 3), (first, 1)],
+            SnippetClick::totals_by_snippet(&conn, day(1), day(2))
+                .unwrap()
+                .iter()
+                .map(|t| (t.snippet_id, t.clicks))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![("links", 5)],
+            SnippetClick::totals_by_taxonomy(&conn, day(1), day(3))
+                .unwrap()
+                .iter()
+                .map(|t| (t.taxonomy.as_str(), t.clicks))
+                .collect::<Vec<_>>()
+        );
+    }
+}
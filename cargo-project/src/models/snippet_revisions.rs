@@ -0,0 +1,332 @@
+use super::{last_insert_rowid, r_to_opt, ModelError, Snippet, Tag};
+use crate::db::DbConn;
+use chrono::NaiveDateTime;
+use diesel::{
+    sql_types::{Nullable, Text},
+    sqlite::Sqlite,
+    Queryable,
+};
+use serde::Serialize;
+
+/// A copy of a snippet as it stood right after it was created or edited.
+/// Revisions are written by `Snippet::create` and `Snippet::update`, so the
+/// newest revision of a snippet always matches the snippet itself.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetRevision {
+    pub id: i32,
+    pub snippet_id: i32,
+
+    /// The user who made this edit, if it was made through the website.
+    pub editor_id: Option<i32>,
+
+    pub taxonomy: String,
+    pub hidden: bool,
+
+    pub title: String,
+    pub icon: String,
+    pub shared_by: String,
+    pub shared_on: NaiveDateTime,
+    pub summary: String,
+    pub description: String,
+    pub href: String,
+
+    /// When the edit was made.
+    pub created_at: NaiveDateTime,
+
+    /// The names of the snippet's tags, or `None` for revisions from before
+    /// tags were kept.
+    #[diesel(deserialize_as = "StoredTags")]
+    pub tags: Option<Vec<String>>,
+
+    /// When the snippet was scheduled to go up, if it was.
+    pub publish_at: Option<NaiveDateTime>,
+}
+
+/// The tags of a revision as they're kept in the database, a JSON array of
+/// names.
+pub struct StoredTags(Option<Vec<String>>);
+
+impl Queryable<Nullable<Text>, Sqlite> for StoredTags {
+    type Row = Option<String>;
+
+    fn build(row: Self::Row) -> Self {
+        StoredTags(row.and_then(|json| serde_json::from_str(&json).ok()))
+    }
+}
+
+impl From<StoredTags> for Option<Vec<String>> {
+    fn from(tags: StoredTags) -> Self {
+        tags.0
+    }
+}
+
+/// One field which differs between two revisions.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    /// The name of the field, as it is named in the API.
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+impl SnippetRevision {
+    /// Saves a copy of the snippet as it is right now, tags and all.
+    pub fn record(
+        conn: &DbConn,
+        snippet: &Snippet,
+        the_editor_id: Option<i32>,
+        the_created_at: &NaiveDateTime,
+    ) -> Result<Self, ModelError> {
+        use crate::schema::snippet_revisions::dsl::{
+            created_at, description, editor_id, hidden, href, icon, id, publish_at, shared_by,
+            shared_on, snippet_id, snippet_revisions, summary, tags, taxonomy, title,
+        };
+        use diesel::prelude::*;
+
+        let revision = conn.transaction::<Self, ModelError, _>(|| {
+            let the_tags = Tag::find_names_by_snippet_id(conn, snippet.id)?;
+
+            diesel::insert_into(snippet_revisions)
+                .values((
+                    snippet_id.eq(snippet.id),
+                    editor_id.eq(the_editor_id),
+                    taxonomy.eq(&snippet.taxonomy),
+                    hidden.eq(snippet.hidden),
+                    title.eq(&snippet.title),
+                    icon.eq(&snippet.icon),
+                    shared_by.eq(&snippet.shared_by),
+                    shared_on.eq(&snippet.shared_on),
+                    summary.eq(&snippet.summary),
+                    description.eq(&snippet.description),
+                    href.eq(&snippet.href),
+                    created_at.eq(the_created_at),
+                    tags.eq(serde_json::Value::from(the_tags).to_string()),
+                    publish_at.eq(&snippet.publish_at),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Ok(snippet_revisions.filter(id.eq(rowid)).first::<Self>(conn)?)
+        })?;
+
+        Ok(revision)
+    }
+
+    /// Brings the tags of a snippet's newest revision up to date. Tags are
+    /// saved separately from the rest of the snippet, often just after the
+    /// edit which changed them was recorded, so this keeps that revision
+    /// matching the snippet.
+    pub fn record_tags(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::snippet_revisions::dsl::{id, snippet_id, snippet_revisions, tags};
+        use diesel::prelude::*;
+
+        let the_tags = Tag::find_names_by_snippet_id(conn, the_snippet_id)?;
+        let newest = snippet_revisions
+            .filter(snippet_id.eq(the_snippet_id))
+            .select(diesel::dsl::max(id))
+            .first::<Option<i32>>(conn)?;
+
+        let r = match newest {
+            Some(newest) => diesel::update(snippet_revisions.find(newest))
+                .set(tags.eq(serde_json::Value::from(the_tags).to_string()))
+                .execute(conn)?,
+            // nothing to keep in step before a snippet's first edit
+            None => 0,
+        };
+
+        Ok(r)
+    }
+
+    /// Finds a revision by its id.
+    pub fn find_by_id(conn: &DbConn, the_id: i32) -> Result<Option<Self>, ModelError> {
+        use crate::schema::snippet_revisions::dsl::{id, snippet_revisions};
+        use diesel::prelude::*;
+
+        let revision = snippet_revisions.filter(id.eq(the_id)).first::<Self>(conn);
+
+        r_to_opt(revision)
+    }
+
+    /// All the revisions of a snippet, newest first.
+    pub fn find_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::snippet_revisions::dsl::{id, snippet_id, snippet_revisions};
+        use diesel::prelude::*;
+
+        let revisions = snippet_revisions
+            .filter(snippet_id.eq(the_snippet_id))
+            .order(id.desc())
+            .load::<Self>(conn)?;
+
+        Ok(revisions)
+    }
+
+    /// Forgets every revision of a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::snippet_revisions::dsl::{snippet_id, snippet_revisions};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(snippet_revisions.filter(snippet_id.eq(the_snippet_id)))
+            .execute(conn)?;
+
+        Ok(r)
+    }
+
+    /// The fields which changed going from this revision to another one.
+    pub fn diff(&self, to: &SnippetRevision) -> Vec<FieldChange> {
+        let fields: Vec<(&'static str, String, String)> = vec![
+            ("taxonomy", self.taxonomy.clone(), to.taxonomy.clone()),
+            ("hidden", self.hidden.to_string(), to.hidden.to_string()),
+            ("title", self.title.clone(), to.title.clone()),
+            ("icon", self.icon.clone(), to.icon.clone()),
+            ("sharedBy", self.shared_by.clone(), to.shared_by.clone()),
+            (
+                "sharedOn",
+                self.shared_on.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                to.shared_on.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ),
+            ("summary", self.summary.clone(), to.summary.clone()),
+            (
+                "description",
+                self.description.clone(),
+                to.description.clone(),
+            ),
+            ("href", self.href.clone(), to.href.clone()),
+            (
+                "publishAt",
+                format_publish_at(&self.publish_at),
+                format_publish_at(&to.publish_at),
+            ),
+        ];
+        // tags can't be compared with a revision that never kept them
+        let tags = match (&self.tags, &to.tags) {
+            (Some(from), Some(to)) => Some(("tags", from.join(", "), to.join(", "))),
+            _ => None,
+        };
+
+        fields
+            .into_iter()
+            .chain(tags)
+            .filter(|(_, from, to)| from != to)
+            .map(|(field, from, to)| FieldChange { field, from, to })
+            .collect()
+    }
+
+    /// Copies the content of this revision back onto a snippet. This does not
+    /// save the snippet, and leaves the tags, which live apart from it, alone;
+    /// `restore` does both.
+    pub fn apply_to(&self, snippet: &mut Snippet) {
+        snippet.taxonomy = self.taxonomy.clone();
+        snippet.hidden = self.hidden;
+        snippet.title = self.title.clone();
+        snippet.icon = self.icon.clone();
+        snippet.shared_by = self.shared_by.clone();
+        snippet.shared_on = self.shared_on;
+        snippet.summary = self.summary.clone();
+        snippet.description = self.description.clone();
+        snippet.href = self.href.clone();
+        snippet.publish_at = self.publish_at;
+    }
+
+    /// Puts a snippet and its tags back the way they were as of this
+    /// revision. The restoration is recorded as a revision of its own.
+    pub fn restore(
+        &self,
+        conn: &DbConn,
+        snippet: &mut Snippet,
+        the_editor_id: Option<i32>,
+    ) -> Result<(), ModelError> {
+        use diesel::prelude::*;
+
+        self.apply_to(snippet);
+
+        conn.transaction::<(), ModelError, _>(|| {
+            snippet.update(conn, the_editor_id)?;
+            if let Some(the_tags) = &self.tags {
+                Tag::set_for_snippet(conn, snippet.id, the_tags)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// How a publish time reads in a diff, blank when there's no schedule.
+fn format_publish_at(publish_at: &Option<NaiveDateTime>) -> String {
+    publish_at
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
+
+    #[test]
+    fn test_revisions() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let mut snippet = create_test_snippet(&conn, "https://example.com/");
+        Tag::set_for_snippet(&conn, snippet.id, &["rust".to_owned()]).unwrap();
+        snippet.title = "Second".to_owned();
+        snippet.hidden = true;
+        snippet.publish_at = Some(
+            NaiveDateTime::parse_from_str("2030-01-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap(),
+        );
+        snippet.update(&conn, Some(2)).unwrap();
+        Tag::set_for_snippet(&conn, snippet.id, &["metal".to_owned()]).unwrap();
+
+        let revisions = SnippetRevision::find_by_snippet_id(&conn, snippet.id).unwrap();
+        assert_eq!(2, revisions.len());
+        assert_eq!(Some(2), revisions[0].editor_id);
+        assert_eq!(Some(1), revisions[1].editor_id);
+        assert_eq!(
+            vec![
+                FieldChange {
+                    field: "hidden",
+                    from: "false".to_owned(),
+                    to: "true".to_owned()
+                },
+                FieldChange {
+                    field: "title",
+                    from: "A link".to_owned(),
+                    to: "Second".to_owned()
+                },
+                FieldChange {
+                    field: "publishAt",
+                    from: "".to_owned(),
+                    to: "2030-01-01T12:00:00".to_owned()
+                },
+                FieldChange {
+                    field: "tags",
+                    from: "rust".to_owned(),
+                    to: "metal".to_owned()
+                },
+            ],
+            revisions[1].diff(&revisions[0])
+        );
+
+        revisions[1].restore(&conn, &mut snippet, None).unwrap();
+        let restored = Snippet::find_by_id(&conn, snippet.id).unwrap();
+        assert_eq!("A link", restored.title);
+        assert_eq!(None, restored.publish_at);
+        assert_eq!(
+            vec!["rust"],
+            Tag::find_names_by_snippet_id(&conn, snippet.id).unwrap()
+        );
+        assert_eq!(
+            Some(vec!["rust".to_owned()]),
+            SnippetRevision::find_by_snippet_id(&conn, snippet.id).unwrap()[0].tags
+        );
+        assert_eq!(
+            3,
+            SnippetRevision::find_by_snippet_id(&conn, snippet.id)
+                .unwrap()
+                .len()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Full-text search over snippets, backed by the `snippets_fts` FTS5 table.
+//! The index is kept in sync with `snippets` by triggers in the database, so
+//! there is nothing to do here on create, update, or delete; this is purely
+//! the read side. Snippets in the trash are never found.
+
+use super::{ModelError, Snippet};
+use crate::db::DbConn;
+use chrono::Utc;
+use diesel::sql_types::{BigInt, Bool, Text, Timestamp};
+use serde::Serialize;
+
+// FTS5 wraps matched terms in these markers. They're control characters
+// which will never show up in a title someone typed, so it's safe to escape
+// everything else as HTML and only then swap these out for real tags.
+const MATCH_START: &str = "\u{2}";
+const MATCH_END: &str = "\u{3}";
+
+/// A single search result: the snippet that matched along with its title
+/// and summary with the matching terms wrapped in `<mark>` tags. The
+/// highlighted fields are HTML-escaped and safe to render as-is. Results come
+/// back ordered by FTS5's bm25 rank, best first.
+#[derive(Debug, QueryableByName, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSearchHit {
+    #[diesel(embed)]
+    pub snippet: Snippet,
+
+    #[sql_type = "Text"]
+    pub title_highlight: String,
+
+    #[sql_type = "Text"]
+    pub summary_highlight: String,
+}
+
+#[derive(QueryableByName)]
+struct SearchCount {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+impl SnippetSearchHit {
+    /// Finds snippets matching the query, best matches first. Returns an
+    /// empty list when the query has no searchable terms. When only visible
+    /// snippets are wanted, snippets in hidden taxonomies are left out too.
+    pub fn search(
+        conn: &DbConn,
+        visible_only: bool,
+        query: &str,
+        page: i64,
+        page_size: i64,
+    ) -> Result<Vec<Self>, ModelError> {
+        use diesel::prelude::*;
+
+        let fts_query = match to_fts_query(query) {
+            Some(q) => q,
+            None => return Ok(vec![]),
+        };
+
+        let hits = diesel::sql_query(
+            "SELECT snippets.*, \
+                highlight(snippets_fts, 0, ?, ?) AS title_highlight, \
+                highlight(snippets_fts, 1, ?, ?) AS summary_highlight \
+            FROM snippets_fts \
+            INNER JOIN snippets ON snippets.id = snippets_fts.rowid \
+            WHERE snippets_fts MATCH ? \
+            AND snippets.deleted_at IS NULL \
+            AND (NOT ? OR (snippets.hidden = 0 \
+                AND (snippets.publish_at IS NULL OR snippets.publish_at <= ?) \
+                AND snippets.taxonomy IN (SELECT slug FROM taxonomies WHERE hidden = 0))) \
+            ORDER BY snippets_fts.rank, snippets.shared_on DESC \
+            LIMIT ? OFFSET ?",
+        )
+        .bind::<Text, _>(MATCH_START)
+        .bind::<Text, _>(MATCH_END)
+        .bind::<Text, _>(MATCH_START)
+        .bind::<Text, _>(MATCH_END)
+        .bind::<Text, _>(&fts_query)
+        .bind::<Bool, _>(visible_only)
+        .bind::<Timestamp, _>(Utc::now().naive_utc())
+        .bind::<BigInt, _>(page_size)
+        .bind::<BigInt, _>(page * page_size)
+        .load::<Self>(conn)?;
+
+        Ok(hits
+            .into_iter()
+            .map(|mut hit| {
+                hit.title_highlight = marks_to_html(&hit.title_highlight);
+                hit.summary_highlight = marks_to_html(&hit.summary_highlight);
+                hit
+            })
+            .collect())
+    }
+
+    /// Counts the snippets matching the query, for pagination.
+    pub fn count(conn: &DbConn, visible_only: bool, query: &str) -> Result<i64, ModelError> {
+        use diesel::prelude::*;
+
+        let fts_query = match to_fts_query(query) {
+            Some(q) => q,
+            None => return Ok(0),
+        };
+
+        let r = diesel::sql_query(
+            "SELECT COUNT(*) AS count \
+            FROM snippets_fts \
+            INNER JOIN snippets ON snippets.id = snippets_fts.rowid \
+            WHERE snippets_fts MATCH ? \
+            AND snippets.deleted_at IS NULL \
+            AND (NOT ? OR (snippets.hidden = 0 \
+                AND (snippets.publish_at IS NULL OR snippets.publish_at <= ?) \
+                AND snippets.taxonomy IN (SELECT slug FROM taxonomies WHERE hidden = 0)))",
+        )
+        .bind::<Text, _>(&fts_query)
+        .bind::<Bool, _>(visible_only)
+        .bind::<Timestamp, _>(Utc::now().naive_utc())
+        .get_result::<SearchCount>(conn)?;
+
+        Ok(r.count)
+    }
+}
+
+/// Turns whatever someone typed into the search box into an FTS5 query.
+/// Raw user input is not safe to hand to MATCH, as stray quotes or operators
+/// are syntax errors, so each word is quoted as its own phrase and treated as
+/// a prefix. Words are implicitly ANDed together.
+fn to_fts_query(query: &str) -> Option<String> {
+    let terms = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Escapes highlighted text as HTML, then turns the match markers into
+/// `<mark>` tags.
+fn marks_to_html(highlighted: &str) -> String {
+    highlighted
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
+
+    fn share(conn: &DbConn, hidden: bool, title: &str, summary: &str) -> Snippet {
+        let mut snippet = create_test_snippet(conn, "https://example.com/");
+        snippet.hidden = hidden;
+        snippet.title = title.to_owned();
+        snippet.summary = summary.to_owned();
+        snippet.update(conn, None).unwrap();
+
+        snippet
+    }
+
+    #[test]
+    fn test_to_fts_query() {
+        assert_eq!(None, to_fts_query("   "));
+        assert_eq!(
+            Some("\"rust\"* \"say\"* \"\"\"hi\"*".to_owned()),
+            to_fts_query("rust say \"hi")
+        );
+    }
+
+    #[test]
+    fn test_search_highlights_and_hides() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        share(&conn, false, "Rendering <b>fast</b>", "about rendering");
+        share(&conn, true, "Rendering in secret", "hush");
+        share(&conn, false, "Audio", "nothing to see");
+
+        let hits = SnippetSearchHit::search(&conn, true, "render", 0, 10).unwrap();
+        assert_eq!(1, hits.len());
+        assert_eq!(
+            "<mark>Rendering</mark> &lt;b&gt;fast&lt;/b&gt;",
+            hits[0].title_highlight
+        );
+        assert_eq!(1, SnippetSearchHit::count(&conn, true, "render").unwrap());
+        assert_eq!(2, SnippetSearchHit::count(&conn, false, "render").unwrap());
+
+        // edits are picked up by the index
+        let mut snippet = Snippet::find_by_id(&conn, hits[0].snippet.id).unwrap();
+        snippet.title = "Sound".to_owned();
+        snippet.summary = "quiet".to_owned();
+        snippet.update(&conn, None).unwrap();
+        assert_eq!(0, SnippetSearchHit::count(&conn, true, "render").unwrap());
+    }
+}
@@ -0,0 +1,115 @@
+use super::ModelError;
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A user voting a snippet up. Users get one vote per snippet, which they can
+/// take back. The snippet's `vote_count` follows along by way of triggers.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetVote {
+    pub id: i32,
+    pub snippet_id: i32,
+    pub user_id: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl SnippetVote {
+    /// Votes the snippet up on behalf of the user. Voting twice is the same as
+    /// voting once.
+    pub fn vote(conn: &DbConn, the_snippet_id: i32, the_user_id: i32) -> Result<(), ModelError> {
+        use crate::schema::snippet_votes::dsl::{created_at, snippet_id, snippet_votes, user_id};
+        use diesel::prelude::*;
+
+        diesel::insert_or_ignore_into(snippet_votes)
+            .values((
+                snippet_id.eq(the_snippet_id),
+                user_id.eq(the_user_id),
+                created_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Takes the user's vote for the snippet back, if they had one.
+    pub fn unvote(conn: &DbConn, the_snippet_id: i32, the_user_id: i32) -> Result<(), ModelError> {
+        use crate::schema::snippet_votes::dsl::{snippet_id, snippet_votes, user_id};
+        use diesel::prelude::*;
+
+        diesel::delete(
+            snippet_votes
+                .filter(snippet_id.eq(the_snippet_id))
+                .filter(user_id.eq(the_user_id)),
+        )
+        .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Which of the given snippets the user has voted up.
+    pub fn find_voted_snippet_ids(
+        conn: &DbConn,
+        the_user_id: i32,
+        the_snippet_ids: &[i32],
+    ) -> Result<HashSet<i32>, ModelError> {
+        use crate::schema::snippet_votes::dsl::{snippet_id, snippet_votes, user_id};
+        use diesel::prelude::*;
+
+        let r = snippet_votes
+            .select(snippet_id)
+            .filter(user_id.eq(the_user_id))
+            .filter(snippet_id.eq_any(the_snippet_ids))
+            .load::<i32>(conn)?;
+
+        Ok(r.into_iter().collect())
+    }
+
+    /// Forgets the votes for a snippet.
+    pub fn delete_by_snippet_id(conn: &DbConn, the_snippet_id: i32) -> Result<usize, ModelError> {
+        use crate::schema::snippet_votes::dsl::{snippet_id, snippet_votes};
+        use diesel::prelude::*;
+
+        let r =
+            diesel::delete(snippet_votes.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_pool,
+        models::{snippets::create_test_snippet, Snippet, SnippetFilter, SnippetSort},
+    };
+
+    #[test]
+    fn test_votes() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let older = create_test_snippet(&conn, "https://example.com/a").id;
+        let newer = create_test_snippet(&conn, "https://example.com/b").id;
+
+        SnippetVote::vote(&conn, older, 1).unwrap();
+        SnippetVote::vote(&conn, older, 1).unwrap();
+        SnippetVote::vote(&conn, older, 2).unwrap();
+        SnippetVote::vote(&conn, newer, 2).unwrap();
+        SnippetVote::unvote(&conn, newer, 2).unwrap();
+        SnippetVote::unvote(&conn, newer, 2).unwrap();
+
+        let top = Snippet::find_filtered(&conn, &SnippetFilter::default(), SnippetSort::Top, 0, 10)
+            .unwrap();
+        assert_eq!(
+            vec![(older, 2), (newer, 0)],
+            top.iter().map(|s| (s.id, s.vote_count)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![older].into_iter().collect::<HashSet<_>>(),
+            SnippetVote::find_voted_snippet_ids(&conn, 1, &[older, newer]).unwrap()
+        );
+    }
+}
@@ -1,10 +1,27 @@
-use super::{last_insert_rowid, ModelError};
-use crate::db::DbConn;
+use super::{
+    last_insert_rowid, r_to_opt,
+    tags::{normalize_names, TagMatch},
+    ModelError, SnippetRevision,
+};
+use crate::{db::DbConn, schema::snippets};
 use chrono::{NaiveDateTime, Utc};
+use diesel::sqlite::Sqlite;
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Queryable, Serialize)]
+const CURSOR_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Query parameters which only say where someone came from, and never change
+/// what a page is. Parameters like `ref` or `s` often do change the page, so
+/// they're left alone, even where they only track.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "igshid", "ref_src", "si"];
+
+/// Prefixes of whole families of tracking parameters, such as `utm_source`.
+const TRACKING_PARAM_PREFIXES: &[&str] = &["utm_", "mc_"];
+
+#[derive(Debug, Deserialize, Queryable, QueryableByName, Serialize)]
 #[serde(rename_all = "camelCase")]
+#[table_name = "snippets"]
 pub struct Snippet {
     pub id: i32,
     pub creator_id: i32,
@@ -22,18 +39,31 @@ pub struct Snippet {
 
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+
+    /// When this snippet was put in the trash. Snippets in the trash are left
+    /// out of everything but the trash itself.
+    pub deleted_at: Option<NaiveDateTime>,
+
+    /// When this snippet goes public. Until then it is treated as hidden by
+    /// everything that only wants visible snippets; after that, `hidden` has
+    /// the final say. `None` means it was public as soon as it was shared.
+    pub publish_at: Option<NaiveDateTime>,
+
+    /// The href with the noise taken out, see `canonicalize_href`. Kept up to
+    /// date by `create` and `update`, whatever the struct says.
+    pub canonical_href: String,
+
+    /// How many users have voted this up. Kept up to date by triggers on
+    /// `snippet_votes`, and never saved from the struct.
+    pub vote_count: i32,
 }
 
 impl Snippet {
-    pub fn count(conn: &DbConn, visible_only: bool, the_taxonomy: &str) -> Result<i64, ModelError> {
-        use crate::schema::snippets::dsl::{hidden, snippets, taxonomy};
-        use diesel::{dsl::count_star, prelude::*};
+    /// How many snippets the filter lets through.
+    pub fn count(conn: &DbConn, filter: &SnippetFilter) -> Result<i64, ModelError> {
+        use diesel::prelude::*;
 
-        let n = snippets
-            .select(count_star())
-            .filter(hidden.eq(!visible_only))
-            .filter(taxonomy.eq(the_taxonomy))
-            .first(conn)?;
+        let n = filter.query().count().get_result(conn)?;
 
         Ok(n)
     }
@@ -50,10 +80,11 @@ impl Snippet {
         the_summary: &str,
         the_description: &str,
         the_href: &str,
+        the_publish_at: Option<&NaiveDateTime>,
     ) -> Result<Self, ModelError> {
         use crate::schema::snippets::dsl::{
-            created_at, creator_id, description, hidden, href, icon, shared_by, shared_on,
-            snippets, summary, taxonomy, title, updated_at,
+            canonical_href, created_at, creator_id, description, hidden, href, icon, publish_at,
+            shared_by, shared_on, snippets, summary, taxonomy, title, updated_at,
         };
         use diesel::prelude::*;
 
@@ -72,111 +103,385 @@ impl Snippet {
                     summary.eq(&the_summary),
                     description.eq(&the_description),
                     href.eq(&the_href),
+                    canonical_href.eq(canonicalize_href(the_href)),
+                    publish_at.eq(the_publish_at),
                     created_at.eq(&inserted_at),
                     updated_at.eq(&inserted_at),
                 ))
                 .execute(conn)?;
 
             let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+            let snippet = Self::find_by_id(conn, rowid)?;
+            SnippetRevision::record(conn, &snippet, Some(the_creator_id), &inserted_at)?;
 
-            Ok(Self::find_by_id(conn, rowid)?)
+            Ok(snippet)
         })?;
 
         Ok(snippet)
     }
 
     pub fn find_by_id(conn: &DbConn, the_id: i32) -> Result<Self, ModelError> {
-        use crate::schema::snippets::dsl::{id, snippets};
+        use crate::schema::snippets::dsl::{deleted_at, id, snippets};
         use diesel::prelude::*;
 
         let snippet = snippets
             .filter(id.eq(the_id))
+            .filter(deleted_at.is_null())
             .limit(1)
             .first::<Snippet>(conn)?;
 
         Ok(snippet)
     }
 
-    #[allow(dead_code)]
+    /// Finds a snippet in the trash by its id.
+    pub fn find_deleted_by_id(conn: &DbConn, the_id: i32) -> Result<Option<Self>, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, id, snippets};
+        use diesel::prelude::*;
+
+        let snippet = snippets
+            .filter(id.eq(the_id))
+            .filter(deleted_at.is_not_null())
+            .first::<Snippet>(conn);
+
+        r_to_opt(snippet)
+    }
+
+    /// Everything in the trash, most recently deleted first.
+    pub fn find_all_deleted(conn: &DbConn) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, snippets};
+        use diesel::prelude::*;
+
+        let r = snippets
+            .filter(deleted_at.is_not_null())
+            .order(deleted_at.desc())
+            .load::<Snippet>(conn)?;
+
+        Ok(r)
+    }
+
+    /// Snippets which are scheduled to go public some time in the future,
+    /// soonest first.
+    pub fn find_scheduled(conn: &DbConn) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, publish_at, snippets};
+        use diesel::prelude::*;
+
+        let r = snippets
+            .filter(deleted_at.is_null())
+            .filter(publish_at.gt(Utc::now().naive_utc()))
+            .order(publish_at.asc())
+            .load::<Snippet>(conn)?;
+
+        Ok(r)
+    }
+
+    /// Finds a snippet not in the trash which links to the same page as the
+    /// given href, going by canonical form.
+    pub fn find_by_canonical_href(
+        conn: &DbConn,
+        the_href: &str,
+    ) -> Result<Option<Self>, ModelError> {
+        use crate::schema::snippets::dsl::{canonical_href, deleted_at, id, snippets};
+        use diesel::prelude::*;
+
+        let snippet = snippets
+            .filter(canonical_href.eq(canonicalize_href(the_href)))
+            .filter(deleted_at.is_null())
+            .order(id.asc())
+            .first::<Snippet>(conn);
+
+        r_to_opt(snippet)
+    }
+
+    /// Whether any snippet links to the same page as the href, counting
+    /// those in the trash, since something thrown away once shouldn't come
+    /// back on its own.
+    pub fn is_href_known(conn: &DbConn, the_href: &str) -> Result<bool, ModelError> {
+        use crate::schema::snippets::dsl::{canonical_href, snippets};
+        use diesel::{dsl::exists, prelude::*};
+
+        let r = diesel::select(exists(
+            snippets.filter(canonical_href.eq(canonicalize_href(the_href))),
+        ))
+        .get_result(conn)?;
+
+        Ok(r)
+    }
+
+    /// Groups of snippets not in the trash which link to the same page, each
+    /// group oldest first.
+    pub fn find_duplicates(conn: &DbConn) -> Result<Vec<Vec<Self>>, ModelError> {
+        use crate::schema::snippets::dsl::{canonical_href, deleted_at, id, snippets};
+        use diesel::prelude::*;
+
+        let rows = snippets
+            .filter(deleted_at.is_null())
+            .order((canonical_href.asc(), id.asc()))
+            .load::<Snippet>(conn)?;
+
+        let mut groups: Vec<Vec<Self>> = Vec::new();
+        for snippet in rows {
+            match groups.last_mut() {
+                Some(group) if group[0].canonical_href == snippet.canonical_href => {
+                    group.push(snippet)
+                }
+                _ => groups.push(vec![snippet]),
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+
+        Ok(groups)
+    }
+
+    /// Recomputes the canonical href of every snippet, for when the rules in
+    /// `canonicalize_href` change. Returns how many changed.
+    pub fn refresh_canonical_hrefs(conn: &DbConn) -> Result<usize, ModelError> {
+        use crate::schema::snippets::dsl::{canonical_href, href, id, snippets};
+        use diesel::prelude::*;
+
+        conn.transaction::<usize, ModelError, _>(|| {
+            let rows = snippets
+                .select((id, href, canonical_href))
+                .load::<(i32, String, String)>(conn)?;
+
+            let mut changed = 0;
+            for (the_id, the_href, the_canonical_href) in rows {
+                let fresh = canonicalize_href(&the_href);
+                if fresh != the_canonical_href {
+                    diesel::update(snippets.find(the_id))
+                        .set(canonical_href.eq(fresh))
+                        .execute(conn)?;
+                    changed += 1;
+                }
+            }
+
+            Ok(changed)
+        })
+    }
+
+    /// The id and href of every snippet not in the trash, for checking that
+    /// the links still go somewhere.
+    pub fn find_all_hrefs(conn: &DbConn) -> Result<Vec<(i32, String)>, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, href, id, snippets};
+        use diesel::prelude::*;
+
+        let r = snippets
+            .filter(deleted_at.is_null())
+            .order(id.asc())
+            .select((id, href))
+            .load::<(i32, String)>(conn)?;
+
+        Ok(r)
+    }
+
+    /// Snippets across every taxonomy, newest first. When only visible
+    /// snippets are wanted this also leaves out everything in a hidden
+    /// taxonomy.
     pub fn find_all(
         conn: &DbConn,
         visible_only: bool,
         page: i64,
         page_size: i64,
     ) -> Result<Vec<Self>, ModelError> {
-        use crate::schema::snippets::dsl::{hidden, shared_on, snippets};
-        use diesel::prelude::*;
-
-        let q = snippets
-            .order(shared_on.desc())
-            .limit(page_size)
-            .offset(page * page_size);
-
-        let r = if visible_only {
-            q.filter(hidden.eq(false)).load::<Snippet>(conn)?
-        } else {
-            q.load::<Snippet>(conn)?
+        let filter = SnippetFilter {
+            visibility: if visible_only {
+                Visibility::Visible
+            } else {
+                Visibility::All
+            },
+            ..SnippetFilter::default()
         };
 
-        Ok(r)
+        Self::find_filtered(conn, &filter, SnippetSort::SharedOn, page, page_size)
     }
 
-    pub fn find_all_by_taxonomy(
+    /// A page of the snippets the filter lets through, in the given order.
+    pub fn find_filtered(
         conn: &DbConn,
-        visible_only: bool,
-        the_taxonomy: &str,
+        filter: &SnippetFilter,
+        sort: SnippetSort,
         page: i64,
         page_size: i64,
     ) -> Result<Vec<Self>, ModelError> {
-        use crate::schema::snippets::dsl::{hidden, shared_on, snippets, taxonomy};
         use diesel::prelude::*;
 
-        let q = snippets
-            .filter(taxonomy.eq(the_taxonomy))
-            .order(shared_on.desc())
+        let r = sort
+            .apply(filter.query())
             .limit(page_size)
-            .offset(page * page_size);
+            .offset(page * page_size)
+            .load::<Snippet>(conn)?;
 
-        let r = if visible_only {
-            q.filter(hidden.eq(false)).load::<Snippet>(conn)?
-        } else {
-            q.load::<Snippet>(conn)?
+        Ok(r)
+    }
+
+    /// Like `find_filtered`, but picking up from a cursor rather than at a
+    /// page number, so that snippets shared in the meantime don't shift
+    /// everything along by one. Starts from the top without a cursor. The
+    /// cursor has to have been made with the same sort.
+    pub fn find_filtered_after(
+        conn: &DbConn,
+        filter: &SnippetFilter,
+        sort: SnippetSort,
+        after: Option<&SnippetCursor>,
+        limit: i64,
+    ) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::snippets::dsl::{
+            created_at, id, shared_on, title, updated_at, vote_count,
         };
+        use diesel::prelude::*;
 
-        Ok(r)
+        let mut q = sort.apply(filter.query()).limit(limit);
+
+        if let Some(after) = after {
+            q = match &after.key {
+                CursorKey::SharedOn(key) => {
+                    q.filter(shared_on.lt(key).or(shared_on.eq(key).and(id.lt(after.id))))
+                }
+                CursorKey::CreatedAt(key) => q.filter(
+                    created_at
+                        .lt(key)
+                        .or(created_at.eq(key).and(id.lt(after.id))),
+                ),
+                CursorKey::UpdatedAt(key) => q.filter(
+                    updated_at
+                        .lt(key)
+                        .or(updated_at.eq(key).and(id.lt(after.id))),
+                ),
+                CursorKey::Title(key) => {
+                    q.filter(title.gt(key).or(title.eq(key).and(id.gt(after.id))))
+                }
+                CursorKey::Votes(key) => q.filter(
+                    vote_count
+                        .lt(key)
+                        .or(vote_count.eq(key).and(id.lt(after.id))),
+                ),
+                CursorKey::Position(key) => q.offset(*key),
+            };
+        }
+
+        Ok(q.load::<Snippet>(conn)?)
     }
 
-    pub fn update(&self, conn: &DbConn) -> Result<(), ModelError> {
+    /// Saves the snippet and records a revision of it, made by the given
+    /// editor. Pass `None` for edits that don't come from a user, such as
+    /// from the command line.
+    pub fn update(&self, conn: &DbConn, the_editor_id: Option<i32>) -> Result<(), ModelError> {
         use crate::schema::snippets::dsl::{
-            creator_id, description, hidden, href, icon, shared_by, shared_on, snippets, summary,
-            taxonomy, title, updated_at,
+            canonical_href, creator_id, description, hidden, href, icon, publish_at, shared_by,
+            shared_on, snippets, summary, taxonomy, title, updated_at,
         };
         use diesel::prelude::*;
 
-        diesel::update(snippets.find(self.id))
-            .set((
-                creator_id.eq(self.creator_id),
-                taxonomy.eq(&self.taxonomy),
-                hidden.eq(self.hidden),
-                title.eq(&self.title),
-                icon.eq(&self.icon),
-                shared_by.eq(&self.shared_by),
-                shared_on.eq(&self.shared_on),
-                summary.eq(&self.summary),
-                description.eq(&self.description),
-                href.eq(&self.href),
-                updated_at.eq(Utc::now().naive_utc()),
-            ))
-            .execute(conn)?;
+        conn.transaction::<(), ModelError, _>(|| {
+            // snippets from before revisions were kept have no history, so
+            // keep what they look like now before it's overwritten.
+            if SnippetRevision::find_by_snippet_id(conn, self.id)?.is_empty() {
+                let original = Self::find_by_id(conn, self.id)?;
+                SnippetRevision::record(
+                    conn,
+                    &original,
+                    Some(original.creator_id),
+                    &original.updated_at,
+                )?;
+            }
+
+            let now = Utc::now().naive_utc();
+
+            diesel::update(snippets.find(self.id))
+                .set((
+                    creator_id.eq(self.creator_id),
+                    taxonomy.eq(&self.taxonomy),
+                    hidden.eq(self.hidden),
+                    title.eq(&self.title),
+                    icon.eq(&self.icon),
+                    shared_by.eq(&self.shared_by),
+                    shared_on.eq(&self.shared_on),
+                    summary.eq(&self.summary),
+                    description.eq(&self.description),
+                    href.eq(&self.href),
+                    canonical_href.eq(canonicalize_href(&self.href)),
+                    publish_at.eq(&self.publish_at),
+                    updated_at.eq(&now),
+                ))
+                .execute(conn)?;
 
-        Ok(())
+            SnippetRevision::record(conn, self, the_editor_id, &now)?;
+
+            Ok(())
+        })
     }
 
+    /// Whether the snippet's publish time, if it has one, has come.
+    pub fn is_published(&self) -> bool {
+        match self.publish_at {
+            Some(t) => t <= Utc::now().naive_utc(),
+            None => true,
+        }
+    }
+
+    /// Puts the snippet in the trash. It stays there, tags, history and all,
+    /// until it is either restored or purged.
     pub fn delete(&self, conn: &DbConn) -> Result<usize, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, snippets};
+        use diesel::prelude::*;
+
+        let r = diesel::update(snippets.find(self.id))
+            .set(deleted_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+
+        Ok(r)
+    }
+
+    /// Takes the snippet back out of the trash.
+    pub fn restore(&self, conn: &DbConn) -> Result<usize, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, snippets};
+        use diesel::prelude::*;
+
+        let r = diesel::update(snippets.find(self.id))
+            .set(deleted_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
+
+        Ok(r)
+    }
+
+    /// Permanently purges every snippet which went into the trash before the
+    /// given time. Returns how many were purged.
+    pub fn purge_deleted_before(
+        conn: &DbConn,
+        the_cutoff: &NaiveDateTime,
+    ) -> Result<usize, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, snippets};
+        use diesel::prelude::*;
+
+        conn.transaction::<usize, ModelError, _>(|| {
+            let doomed = snippets
+                .filter(deleted_at.lt(the_cutoff))
+                .load::<Snippet>(conn)?;
+
+            for snippet in &doomed {
+                snippet.purge(conn)?;
+            }
+
+            Ok(doomed.len())
+        })
+    }
+
+    /// Permanently removes the snippet along with its tags and history. There
+    /// is no coming back from this.
+    pub fn purge(&self, conn: &DbConn) -> Result<usize, ModelError> {
         use crate::schema::snippets::dsl::{id, snippets};
         use diesel::prelude::*;
 
-        let r = diesel::delete(snippets.filter(id.eq(self.id))).execute(conn)?;
+        let r = conn.transaction::<usize, ModelError, _>(|| {
+            super::Tag::remove_all_from_snippet(conn, self.id)?;
+            SnippetRevision::delete_by_snippet_id(conn, self.id)?;
+            super::LinkCheck::delete_by_snippet_id(conn, self.id)?;
+            super::SnippetVote::delete_by_snippet_id(conn, self.id)?;
+            super::Comment::delete_by_snippet_id(conn, self.id)?;
+            super::Bookmark::delete_by_snippet_id(conn, self.id)?;
+            super::SnippetClick::delete_by_snippet_id(conn, self.id)?;
+
+            Ok(diesel::delete(snippets.filter(id.eq(self.id))).execute(conn)?)
+        })?;
 
         Ok(r)
     }
@@ -198,6 +503,637 @@ impl Default for Snippet {
             href: "".to_owned(),
             created_at: Utc::now().naive_utc(),
             updated_at: Utc::now().naive_utc(),
+            deleted_at: None,
+            publish_at: None,
+            canonical_href: "".to_owned(),
+            vote_count: 0,
+        }
+    }
+}
+
+/// Which snippets show up in a list, by whether they're visible to everyone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    /// Only snippets everyone can see: not hidden, and already published.
+    Visible,
+
+    /// Only snippets which are hidden or yet to be published.
+    Hidden,
+
+    /// Every snippet, hidden or not.
+    All,
+}
+
+/// Narrows down a list of snippets. The same filter is used to both list and
+/// count snippets so that the two always agree on what they're looking at.
+/// Snippets in the trash never get through.
+#[derive(Clone, Debug)]
+pub struct SnippetFilter {
+    /// Only snippets in this taxonomy. Without one, snippets in any taxonomy,
+    /// except hidden ones when only visible snippets are wanted.
+    pub taxonomy: Option<String>,
+
+    /// Only snippets with these tags, all of them or any of them depending on
+    /// `tag_match`. No tags lets every snippet through.
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+
+    pub visibility: Visibility,
+
+    /// Only snippets shared by this person, as they were credited.
+    pub shared_by: Option<String>,
+
+    /// Only snippets posted by this user.
+    pub creator_id: Option<i32>,
+
+    /// Only snippets shared on or after this.
+    pub shared_since: Option<NaiveDateTime>,
+
+    /// Only snippets shared before this.
+    pub shared_until: Option<NaiveDateTime>,
+
+    /// Only snippets with this icon.
+    pub icon: Option<String>,
+
+    /// Only snippets this user has bookmarked.
+    pub bookmarked_by: Option<i32>,
+}
+
+impl Default for SnippetFilter {
+    /// Every visible snippet.
+    fn default() -> Self {
+        Self {
+            taxonomy: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::All,
+            visibility: Visibility::Visible,
+            shared_by: None,
+            creator_id: None,
+            shared_since: None,
+            shared_until: None,
+            icon: None,
+            bookmarked_by: None,
+        }
+    }
+}
+
+impl SnippetFilter {
+    /// Every visible snippet in a taxonomy.
+    pub fn in_taxonomy(the_taxonomy: &str) -> Self {
+        Self {
+            taxonomy: Some(the_taxonomy.to_owned()),
+            ..Self::default()
+        }
+    }
+
+    fn query(&self) -> snippets::BoxedQuery<'_, Sqlite> {
+        use crate::schema::{bookmarks, snippet_tags, snippets::dsl::*, tags, taxonomies};
+        use diesel::prelude::*;
+
+        let now = Utc::now().naive_utc();
+        let mut q = snippets.filter(deleted_at.is_null()).into_boxed();
+
+        match &self.taxonomy {
+            Some(the_taxonomy) => q = q.filter(taxonomy.eq(the_taxonomy)),
+            None if self.visibility == Visibility::Visible => {
+                q = q.filter(
+                    taxonomy.eq_any(
+                        taxonomies::table
+                            .filter(taxonomies::hidden.eq(false))
+                            .select(taxonomies::slug),
+                    ),
+                )
+            }
+            None => {}
+        }
+
+        match self.visibility {
+            Visibility::Visible => {
+                q = q
+                    .filter(hidden.eq(false))
+                    .filter(publish_at.is_null().or(publish_at.le(now)))
+            }
+            Visibility::Hidden => q = q.filter(hidden.eq(true).or(publish_at.gt(now))),
+            Visibility::All => {}
+        }
+
+        if let Some(the_shared_by) = &self.shared_by {
+            q = q.filter(shared_by.eq(the_shared_by));
+        }
+        if let Some(the_creator_id) = self.creator_id {
+            q = q.filter(creator_id.eq(the_creator_id));
+        }
+        if let Some(since) = self.shared_since {
+            q = q.filter(shared_on.ge(since));
+        }
+        if let Some(until) = self.shared_until {
+            q = q.filter(shared_on.lt(until));
+        }
+        if let Some(the_icon) = &self.icon {
+            q = q.filter(icon.eq(the_icon));
+        }
+        if let Some(user_id) = self.bookmarked_by {
+            q = q.filter(
+                id.eq_any(
+                    bookmarks::table
+                        .filter(bookmarks::user_id.eq(user_id))
+                        .select(bookmarks::snippet_id),
+                ),
+            );
+        }
+
+        let names = normalize_names(&self.tags);
+        if names.is_empty() {
+            return q;
+        }
+
+        match self.tag_match {
+            TagMatch::Any => {
+                q = q.filter(
+                    id.eq_any(
+                        snippet_tags::table
+                            .inner_join(tags::table)
+                            .filter(tags::name.eq_any(names))
+                            .select(snippet_tags::snippet_id),
+                    ),
+                );
+            }
+            TagMatch::All => {
+                for name in names {
+                    q = q.filter(
+                        id.eq_any(
+                            snippet_tags::table
+                                .inner_join(tags::table)
+                                .filter(tags::name.eq(name))
+                                .select(snippet_tags::snippet_id),
+                        ),
+                    );
+                }
+            }
+        }
+
+        q
+    }
+}
+
+/// How a snippet's hot score is worked out: its votes, plus one so that new
+/// snippets without any still get a look in, over the square of its age in
+/// hours. The two hours added to the age keep the newest snippets from
+/// shooting straight to the top.
+const HOT_SCORE: &str = "(vote_count + 1) / \
+    (((julianday('now') - julianday(shared_on)) * 24 + 2) * \
+    ((julianday('now') - julianday(shared_on)) * 24 + 2))";
+
+/// The order of a list of snippets. Dates go newest first and titles go
+/// alphabetically, with the id settling ties the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SnippetSort {
+    SharedOn,
+    CreatedAt,
+    UpdatedAt,
+    Title,
+
+    /// Most votes first.
+    Top,
+
+    /// Highest hot score first, see `HOT_SCORE`, so that votes count for
+    /// less the older a snippet gets.
+    Hot,
+}
+
+impl SnippetSort {
+    fn apply(self, q: snippets::BoxedQuery<'_, Sqlite>) -> snippets::BoxedQuery<'_, Sqlite> {
+        use crate::schema::snippets::dsl::{
+            created_at, id, shared_on, title, updated_at, vote_count,
+        };
+        use diesel::{dsl::sql, prelude::*, sql_types::Double};
+
+        match self {
+            SnippetSort::SharedOn => q.order((shared_on.desc(), id.desc())),
+            SnippetSort::CreatedAt => q.order((created_at.desc(), id.desc())),
+            SnippetSort::UpdatedAt => q.order((updated_at.desc(), id.desc())),
+            SnippetSort::Title => q.order((title.asc(), id.asc())),
+            SnippetSort::Top => q.order((vote_count.desc(), id.desc())),
+            SnippetSort::Hot => q.order((sql::<Double>(HOT_SCORE).desc(), id.desc())),
+        }
+    }
+}
+
+/// The value of the sort key a cursor was made from.
+#[derive(Debug, PartialEq)]
+enum CursorKey {
+    SharedOn(NaiveDateTime),
+    CreatedAt(NaiveDateTime),
+    UpdatedAt(NaiveDateTime),
+    Title(String),
+    Votes(i32),
+
+    /// How many snippets came before, for sorts which move around too much
+    /// to pick up from a key.
+    Position(i64),
+}
+
+/// A place in a sorted list of snippets, just past a given snippet. Handed to
+/// clients as an opaque token so that they don't come to depend on what's
+/// inside.
+#[derive(Debug, PartialEq)]
+pub struct SnippetCursor {
+    key: CursorKey,
+    id: i32,
+}
+
+impl SnippetCursor {
+    /// The place just past this snippet in a list sorted the given way.
+    /// `position` is how far down the list the snippet is, counting from one,
+    /// which is all there is to go by for hot snippets since their scores
+    /// change by the minute.
+    pub fn after(snippet: &Snippet, sort: SnippetSort, position: i64) -> Self {
+        let key = match sort {
+            SnippetSort::SharedOn => CursorKey::SharedOn(snippet.shared_on),
+            SnippetSort::CreatedAt => CursorKey::CreatedAt(snippet.created_at),
+            SnippetSort::UpdatedAt => CursorKey::UpdatedAt(snippet.updated_at),
+            SnippetSort::Title => CursorKey::Title(snippet.title.clone()),
+            SnippetSort::Top => CursorKey::Votes(snippet.vote_count),
+            SnippetSort::Hot => CursorKey::Position(position),
+        };
+
+        Self {
+            key,
+            id: snippet.id,
         }
     }
+
+    /// The sort this cursor belongs to.
+    pub fn sort(&self) -> SnippetSort {
+        match self.key {
+            CursorKey::SharedOn(_) => SnippetSort::SharedOn,
+            CursorKey::CreatedAt(_) => SnippetSort::CreatedAt,
+            CursorKey::UpdatedAt(_) => SnippetSort::UpdatedAt,
+            CursorKey::Title(_) => SnippetSort::Title,
+            CursorKey::Votes(_) => SnippetSort::Top,
+            CursorKey::Position(_) => SnippetSort::Hot,
+        }
+    }
+
+    /// How many snippets come before this place in the list, if the cursor
+    /// knows.
+    pub fn position(&self) -> Option<i64> {
+        match self.key {
+            CursorKey::Position(position) => Some(position),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let (name, key) = match &self.key {
+            CursorKey::SharedOn(key) => ("s", key.format(CURSOR_DATE_FORMAT).to_string()),
+            CursorKey::CreatedAt(key) => ("c", key.format(CURSOR_DATE_FORMAT).to_string()),
+            CursorKey::UpdatedAt(key) => ("u", key.format(CURSOR_DATE_FORMAT).to_string()),
+            CursorKey::Title(key) => ("t", key.clone()),
+            CursorKey::Votes(key) => ("v", key.to_string()),
+            CursorKey::Position(key) => ("p", key.to_string()),
+        };
+        // the key goes last, since a title could have anything in it
+        let raw = format!("{}|{}|{}", name, self.id, key);
+        base64::encode_config(raw, base64::URL_SAFE_NO_PAD)
+    }
+
+    /// Reads a token made by `encode`, or `None` if it isn't one.
+    pub fn decode(token: &str) -> Option<Self> {
+        let raw = base64::decode_config(token, base64::URL_SAFE_NO_PAD).ok()?;
+        let raw = String::from_utf8(raw).ok()?;
+        let mut parts = raw.splitn(3, '|');
+        let (name, id, key) = (parts.next()?, parts.next()?, parts.next()?);
+        let date = || NaiveDateTime::parse_from_str(key, CURSOR_DATE_FORMAT).ok();
+
+        let key = match name {
+            "s" => CursorKey::SharedOn(date()?),
+            "c" => CursorKey::CreatedAt(date()?),
+            "u" => CursorKey::UpdatedAt(date()?),
+            "t" => CursorKey::Title(key.to_owned()),
+            "v" => CursorKey::Votes(key.parse().ok()?),
+            "p" => CursorKey::Position(key.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(Self {
+            key,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+/// Boils an href down to the page it points at, so that the same page shared
+/// twice under different URLs can be caught. http and https are treated
+/// alike, as are hosts with and without `www.`; fragments, tracking
+/// parameters, and trailing slashes are dropped; and the remaining query
+/// parameters are sorted. Anything which isn't a URL is only trimmed.
+pub fn canonicalize_href(href: &str) -> String {
+    let url = match Url::parse(href.trim()) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+        _ => return href.trim().to_owned(),
+    };
+
+    let host = url.host_str().unwrap_or("");
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let port = match url.port() {
+        // 80 is already dropped by the parser, but not on https
+        Some(443) | None => "".to_owned(),
+        Some(port) => format!(":{}", port),
+    };
+    let path = url.path().trim_end_matches('/');
+
+    let mut params = url
+        .query_pairs()
+        .filter(|(key, _)| {
+            !TRACKING_PARAMS.contains(&key.as_ref())
+                && !TRACKING_PARAM_PREFIXES
+                    .iter()
+                    .any(|prefix| key.starts_with(prefix))
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    params.sort();
+    let query = if params.is_empty() {
+        "".to_owned()
+    } else {
+        let mut query = Url::parse("https://example.com/").unwrap();
+        query.query_pairs_mut().extend_pairs(params);
+        format!("?{}", query.query().unwrap_or(""))
+    };
+
+    format!("https://{}{}{}{}", host, port, path, query)
+}
+
+/// A snippet with nothing remarkable about it, for tests which only need one
+/// to exist. Tests which care about anything but its href change the rest
+/// and update it.
+#[cfg(test)]
+pub fn create_test_snippet(conn: &DbConn, the_href: &str) -> Snippet {
+    Snippet::create(
+        conn,
+        1,
+        "links",
+        false,
+        "safari.png",
+        "A link",
+        "someone",
+        &Utc::now().naive_utc(),
+        "summary",
+        "",
+        the_href,
+        None,
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+    use chrono::Duration;
+
+    #[test]
+    fn test_trash() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let snippet = create_test_snippet(&conn, "https://example.com/");
+        snippet.delete(&conn).unwrap();
+
+        assert!(Snippet::find_by_id(&conn, snippet.id).is_err());
+        assert!(Snippet::find_all(&conn, false, 0, 10).unwrap().is_empty());
+        assert_eq!(1, Snippet::find_all_deleted(&conn).unwrap().len());
+
+        let trashed = Snippet::find_deleted_by_id(&conn, snippet.id)
+            .unwrap()
+            .unwrap();
+        trashed.restore(&conn).unwrap();
+        assert!(Snippet::find_by_id(&conn, snippet.id).is_ok());
+
+        // purging only touches what's in the trash, and only old enough trash
+        snippet.delete(&conn).unwrap();
+        let an_hour_ago = Utc::now().naive_utc() - Duration::hours(1);
+        assert_eq!(
+            0,
+            Snippet::purge_deleted_before(&conn, &an_hour_ago).unwrap()
+        );
+        let in_an_hour = Utc::now().naive_utc() + Duration::hours(1);
+        assert_eq!(
+            1,
+            Snippet::purge_deleted_before(&conn, &in_an_hour).unwrap()
+        );
+        assert!(Snippet::find_deleted_by_id(&conn, snippet.id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_scheduled() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let tomorrow = Utc::now().naive_utc() + Duration::days(1);
+        let mut snippet = create_test_snippet(&conn, "https://example.com/");
+        snippet.publish_at = Some(tomorrow);
+        snippet.update(&conn, None).unwrap();
+
+        assert!(!snippet.is_published());
+        assert!(Snippet::find_all(&conn, true, 0, 10).unwrap().is_empty());
+        assert_eq!(1, Snippet::find_all(&conn, false, 0, 10).unwrap().len());
+        assert_eq!(
+            0,
+            Snippet::count(&conn, &SnippetFilter::in_taxonomy("links")).unwrap()
+        );
+        assert_eq!(1, Snippet::find_scheduled(&conn).unwrap().len());
+
+        snippet.publish_at = Some(Utc::now().naive_utc() - Duration::days(1));
+        snippet.update(&conn, None).unwrap();
+        assert_eq!(1, Snippet::find_all(&conn, true, 0, 10).unwrap().len());
+        assert!(Snippet::find_scheduled(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_canonicalize_href() {
+        for href in &[
+            "https://example.com/a",
+            "http://example.com/a",
+            "https://www.example.com/a/",
+            "https://EXAMPLE.com:443/a#comments",
+            " https://example.com/a?utm_source=discord&fbclid=xyz ",
+            "https://example.com/a?mc_cid=1&mc_eid=2&ref_src=twsrc",
+        ] {
+            assert_eq!("https://example.com/a", canonicalize_href(href));
+        }
+        assert_eq!(
+            "https://example.com/watch?b=2&v=1",
+            canonicalize_href("https://example.com/watch?v=1&b=2&si=abc")
+        );
+        assert_eq!(
+            "https://github.com/a/b?ref=v1.0",
+            canonicalize_href("https://github.com/a/b?ref=v1.0")
+        );
+        assert_eq!(
+            "https://example.com?s=rust",
+            canonicalize_href("https://example.com/?s=rust")
+        );
+        assert_eq!(
+            "https://example.com",
+            canonicalize_href("http://example.com/")
+        );
+        assert_eq!("not a url", canonicalize_href(" not a url "));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let share = |href: &str| create_test_snippet(&conn, href);
+        let first = share("https://example.com/a");
+        let second = share("http://www.example.com/a/?utm_medium=social");
+        share("https://example.com/b");
+
+        assert_eq!(
+            Some(first.id),
+            Snippet::find_by_canonical_href(&conn, "https://example.com/a#top")
+                .unwrap()
+                .map(|s| s.id)
+        );
+        let groups = Snippet::find_duplicates(&conn).unwrap();
+        assert_eq!(
+            vec![vec![first.id, second.id]],
+            groups
+                .iter()
+                .map(|group| group.iter().map(|s| s.id).collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        // three snippets shared at the same moment, so that only the id can
+        // tell them apart
+        let shared_on = Utc::now().naive_utc();
+        for title in &["First", "Second", "Third"] {
+            let mut snippet = create_test_snippet(&conn, "https://example.com/");
+            snippet.title = title.to_string();
+            snippet.shared_on = shared_on;
+            snippet.update(&conn, None).unwrap();
+        }
+
+        let filter = SnippetFilter::in_taxonomy("links");
+        let page = |sort: SnippetSort, after: Option<&SnippetCursor>| {
+            Snippet::find_filtered_after(&conn, &filter, sort, after, 2)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["Third", "Second"], page(SnippetSort::SharedOn, None));
+
+        let second = Snippet::find_by_id(&conn, 2).unwrap();
+        let after = SnippetCursor::after(&second, SnippetSort::SharedOn, 2);
+        let cursor = SnippetCursor::decode(&after.encode()).unwrap();
+        assert_eq!(after, cursor);
+        assert_eq!(vec!["First"], page(SnippetSort::SharedOn, Some(&cursor)));
+
+        let after = SnippetCursor::after(&second, SnippetSort::Title, 2);
+        let cursor = SnippetCursor::decode(&after.encode()).unwrap();
+        assert_eq!(SnippetSort::Title, cursor.sort());
+        assert_eq!(vec!["Third"], page(SnippetSort::Title, Some(&cursor)));
+
+        assert_eq!(None, SnippetCursor::decode("not a cursor"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let shared_on =
+            NaiveDateTime::parse_from_str("2021-06-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let share = |title: &str, is_hidden: bool, sharer: &str, days_ago: i64| {
+            let mut snippet = create_test_snippet(&conn, "https://example.com/");
+            snippet.title = title.to_owned();
+            snippet.hidden = is_hidden;
+            snippet.shared_by = sharer.to_owned();
+            snippet.shared_on = shared_on - Duration::days(days_ago);
+            snippet.update(&conn, None).unwrap();
+
+            snippet
+        };
+        share("Banana", false, "alice", 0);
+        share("Apple", false, "bob", 10);
+        share("Cherry", true, "alice", 20);
+
+        let count = |filter: SnippetFilter| Snippet::count(&conn, &filter).unwrap();
+        let titles = |filter: SnippetFilter, sort: SnippetSort| {
+            Snippet::find_filtered(&conn, &filter, sort, 0, 10)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.title)
+                .collect::<Vec<_>>()
+        };
+        let links = SnippetFilter::in_taxonomy("links");
+
+        assert_eq!(2, count(links.clone()));
+        assert_eq!(
+            1,
+            count(SnippetFilter {
+                visibility: Visibility::Hidden,
+                ..links.clone()
+            })
+        );
+        assert_eq!(
+            3,
+            count(SnippetFilter {
+                visibility: Visibility::All,
+                ..links.clone()
+            })
+        );
+        assert_eq!(
+            vec!["Banana", "Cherry"],
+            titles(
+                SnippetFilter {
+                    visibility: Visibility::All,
+                    shared_by: Some("alice".to_owned()),
+                    ..links.clone()
+                },
+                SnippetSort::SharedOn
+            )
+        );
+        assert_eq!(
+            vec!["Apple"],
+            titles(
+                SnippetFilter {
+                    visibility: Visibility::All,
+                    shared_since: Some(shared_on - Duration::days(15)),
+                    shared_until: Some(shared_on - Duration::days(5)),
+                    ..links.clone()
+                },
+                SnippetSort::SharedOn
+            )
+        );
+        assert_eq!(
+            vec!["Apple", "Banana", "Cherry"],
+            titles(
+                SnippetFilter {
+                    visibility: Visibility::All,
+                    ..links.clone()
+                },
+                SnippetSort::Title
+            )
+        );
+        assert_eq!(
+            0,
+            count(SnippetFilter {
+                icon: Some("github.png".to_owned()),
+                ..links
+            })
+        );
+    }
 }
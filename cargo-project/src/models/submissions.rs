@@ -0,0 +1,271 @@
+use super::{last_insert_rowid, r_to_opt, snippets::canonicalize_href, ModelError};
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+
+/// A submission nobody has looked at yet.
+pub const PENDING: &str = "pending";
+
+/// A submission an admin turned into a snippet.
+pub const APPROVED: &str = "approved";
+
+/// A submission an admin turned down.
+pub const REJECTED: &str = "rejected";
+
+/// A link shared by someone who isn't an admin, which waits in a queue for an
+/// admin to either make a snippet of it or turn it down. The submission is
+/// kept either way so that whoever sent it in can see how it went.
+#[derive(Debug, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Submission {
+    pub id: i32,
+
+    /// The user who sent this in, who becomes the snippet's creator.
+    pub submitter_id: i32,
+
+    pub taxonomy: String,
+    pub title: String,
+    pub shared_by: String,
+    pub summary: String,
+    pub description: String,
+    pub href: String,
+    pub canonical_href: String,
+
+    /// One of `PENDING`, `APPROVED`, or `REJECTED`.
+    pub status: String,
+
+    /// Why the submission was turned down, as told to the submitter.
+    pub rejection_reason: Option<String>,
+
+    /// The snippet this became, once approved.
+    pub snippet_id: Option<i32>,
+
+    /// The admin who approved or rejected this.
+    pub reviewer_id: Option<i32>,
+    pub reviewed_at: Option<NaiveDateTime>,
+
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// What someone sends in, before it's in the queue.
+#[derive(Debug)]
+pub struct NewSubmission<'a> {
+    pub submitter_id: i32,
+    pub taxonomy: &'a str,
+    pub title: &'a str,
+    pub shared_by: &'a str,
+    pub summary: &'a str,
+    pub description: &'a str,
+    pub href: &'a str,
+}
+
+impl Submission {
+    pub fn create(conn: &DbConn, new_submission: &NewSubmission) -> Result<Self, ModelError> {
+        use crate::schema::submissions::dsl::{
+            canonical_href, created_at, description, href, shared_by, submissions, submitter_id,
+            summary, taxonomy, title, updated_at,
+        };
+        use diesel::prelude::*;
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            let now = Utc::now().naive_utc();
+
+            diesel::insert_into(submissions)
+                .values((
+                    submitter_id.eq(new_submission.submitter_id),
+                    taxonomy.eq(new_submission.taxonomy),
+                    title.eq(new_submission.title),
+                    shared_by.eq(new_submission.shared_by),
+                    summary.eq(new_submission.summary),
+                    description.eq(new_submission.description),
+                    href.eq(new_submission.href),
+                    canonical_href.eq(canonicalize_href(new_submission.href)),
+                    created_at.eq(&now),
+                    updated_at.eq(&now),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Self::find_by_id(conn, rowid)
+        })
+    }
+
+    pub fn find_by_id(conn: &DbConn, the_id: i32) -> Result<Self, ModelError> {
+        use crate::schema::submissions::dsl::submissions;
+        use diesel::prelude::*;
+
+        Ok(submissions.find(the_id).first::<Self>(conn)?)
+    }
+
+    /// Submissions with the given status, oldest first, which for pending
+    /// submissions is the order they ought to be looked at in.
+    pub fn find_by_status(conn: &DbConn, the_status: &str) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::submissions::dsl::{created_at, id, status, submissions};
+        use diesel::prelude::*;
+
+        let r = submissions
+            .filter(status.eq(the_status))
+            .order((created_at.asc(), id.asc()))
+            .load::<Self>(conn)?;
+
+        Ok(r)
+    }
+
+    /// Everything a user has sent in, newest first.
+    pub fn find_by_submitter_id(
+        conn: &DbConn,
+        the_submitter_id: i32,
+    ) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::submissions::dsl::{created_at, id, submissions, submitter_id};
+        use diesel::prelude::*;
+
+        let r = submissions
+            .filter(submitter_id.eq(the_submitter_id))
+            .order((created_at.desc(), id.desc()))
+            .load::<Self>(conn)?;
+
+        Ok(r)
+    }
+
+    /// A pending submission of the same page as the href, if someone beat
+    /// the submitter to it.
+    pub fn find_pending_by_canonical_href(
+        conn: &DbConn,
+        the_href: &str,
+    ) -> Result<Option<Self>, ModelError> {
+        use crate::schema::submissions::dsl::{canonical_href, id, status, submissions};
+        use diesel::prelude::*;
+
+        let submission = submissions
+            .filter(canonical_href.eq(canonicalize_href(the_href)))
+            .filter(status.eq(PENDING))
+            .order(id.asc())
+            .first::<Self>(conn);
+
+        r_to_opt(submission)
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == PENDING
+    }
+
+    /// Marks this as having become the given snippet.
+    pub fn approve(
+        &self,
+        conn: &DbConn,
+        the_reviewer_id: i32,
+        the_snippet_id: i32,
+    ) -> Result<(), ModelError> {
+        use crate::schema::submissions::dsl::{
+            reviewed_at, reviewer_id, snippet_id, status, submissions, updated_at,
+        };
+        use diesel::prelude::*;
+
+        let now = Utc::now().naive_utc();
+        diesel::update(submissions.find(self.id))
+            .set((
+                status.eq(APPROVED),
+                snippet_id.eq(the_snippet_id),
+                reviewer_id.eq(the_reviewer_id),
+                reviewed_at.eq(&now),
+                updated_at.eq(&now),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Turns this down, telling the submitter why.
+    pub fn reject(
+        &self,
+        conn: &DbConn,
+        the_reviewer_id: i32,
+        the_reason: &str,
+    ) -> Result<(), ModelError> {
+        use crate::schema::submissions::dsl::{
+            rejection_reason, reviewed_at, reviewer_id, status, submissions, updated_at,
+        };
+        use diesel::prelude::*;
+
+        let now = Utc::now().naive_utc();
+        diesel::update(submissions.find(self.id))
+            .set((
+                status.eq(REJECTED),
+                rejection_reason.eq(the_reason),
+                reviewer_id.eq(the_reviewer_id),
+                reviewed_at.eq(&now),
+                updated_at.eq(&now),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[test]
+    fn test_moderation() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let submit = |href: &str| {
+            Submission::create(
+                &conn,
+                &NewSubmission {
+                    submitter_id: 2,
+                    taxonomy: "links",
+                    title: "A link",
+                    shared_by: "someone",
+                    summary: "summary",
+                    description: "",
+                    href,
+                },
+            )
+            .unwrap()
+        };
+        let first = submit("https://example.com/a");
+        let second = submit("https://example.com/b");
+
+        assert_eq!(
+            Some(first.id),
+            Submission::find_pending_by_canonical_href(&conn, "http://www.example.com/a/")
+                .unwrap()
+                .map(|s| s.id)
+        );
+        assert_eq!(2, Submission::find_by_status(&conn, PENDING).unwrap().len());
+
+        first.approve(&conn, 1, 10).unwrap();
+        second.reject(&conn, 1, "Not about games").unwrap();
+
+        assert!(Submission::find_by_status(&conn, PENDING)
+            .unwrap()
+            .is_empty());
+        assert!(
+            Submission::find_pending_by_canonical_href(&conn, "https://example.com/a")
+                .unwrap()
+                .is_none()
+        );
+
+        let mine = Submission::find_by_submitter_id(&conn, 2).unwrap();
+        assert_eq!(
+            vec![
+                (second.id, REJECTED, None, Some("Not about games")),
+                (first.id, APPROVED, Some(10), None),
+            ],
+            mine.iter()
+                .map(|s| (
+                    s.id,
+                    s.status.as_str(),
+                    s.snippet_id,
+                    s.rejection_reason.as_deref()
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+}
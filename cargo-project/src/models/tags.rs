@@ -0,0 +1,210 @@
+use super::{last_insert_rowid, r_to_opt, ModelError, SnippetRevision};
+use crate::db::DbConn;
+use std::collections::HashMap;
+
+/// A free-form label on a snippet. Unlike a taxonomy, of which a snippet has
+/// exactly one, a snippet may have any number of tags, so a link can be both
+/// "rust" and "rendering" at once. Tags are known by their name, which is
+/// always trimmed and lowercase. Only the id is loaded, since the names are
+/// always fetched alongside the snippets they're on.
+#[derive(Debug, Queryable)]
+pub struct Tag {
+    /// Id of this tag.
+    pub id: i32,
+}
+
+/// How a list of tags narrows down a list of snippets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TagMatch {
+    /// Snippets must have every one of the tags.
+    All,
+
+    /// Snippets must have at least one of the tags.
+    Any,
+}
+
+impl Tag {
+    /// Finds a tag by its name, creating it if it does not exist yet. The
+    /// name is normalized first.
+    pub fn find_or_create(conn: &DbConn, the_name: &str) -> Result<Self, ModelError> {
+        use crate::schema::tags::dsl::{id, name, tags};
+        use diesel::prelude::*;
+
+        let the_name = normalize_name(the_name);
+
+        conn.transaction::<Self, ModelError, _>(|| {
+            if let Some(tag) = Self::find_by_name(conn, &the_name)? {
+                return Ok(tag);
+            }
+
+            diesel::insert_into(tags)
+                .values(name.eq(&the_name))
+                .execute(conn)?;
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Ok(tags
+                .filter(id.eq(rowid))
+                .select((id,))
+                .first::<Self>(conn)?)
+        })
+    }
+
+    /// Finds a tag by its name, which must already be normalized.
+    pub fn find_by_name(conn: &DbConn, the_name: &str) -> Result<Option<Self>, ModelError> {
+        use crate::schema::tags::dsl::{id, name, tags};
+        use diesel::prelude::*;
+
+        let tag = tags
+            .filter(name.eq(the_name))
+            .select((id,))
+            .first::<Self>(conn);
+
+        r_to_opt(tag)
+    }
+
+    /// The names of all the tags on a snippet, alphabetically.
+    pub fn find_names_by_snippet_id(
+        conn: &DbConn,
+        the_snippet_id: i32,
+    ) -> Result<Vec<String>, ModelError> {
+        Ok(Self::find_names_by_snippet_ids(conn, &[the_snippet_id])?
+            .remove(&the_snippet_id)
+            .unwrap_or_default())
+    }
+
+    /// The names of all the tags on several snippets at once, keyed by
+    /// snippet id. Snippets with no tags are absent from the map.
+    pub fn find_names_by_snippet_ids(
+        conn: &DbConn,
+        the_snippet_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<String>>, ModelError> {
+        use crate::schema::{snippet_tags, tags};
+        use diesel::prelude::*;
+
+        let rows = snippet_tags::table
+            .inner_join(tags::table)
+            .filter(snippet_tags::snippet_id.eq_any(the_snippet_ids))
+            .order(tags::name.asc())
+            .select((snippet_tags::snippet_id, tags::name))
+            .load::<(i32, String)>(conn)?;
+
+        let mut names: HashMap<i32, Vec<String>> = HashMap::new();
+        for (the_snippet_id, the_name) in rows {
+            names.entry(the_snippet_id).or_default().push(the_name);
+        }
+
+        Ok(names)
+    }
+
+    /// Replaces all the tags on a snippet with the given ones, creating any
+    /// tags which don't exist yet. Names are normalized first, so `" Rust"`
+    /// and `"rust"` are the same tag. The snippet's newest revision is given
+    /// the new tags too.
+    pub fn set_for_snippet(
+        conn: &DbConn,
+        the_snippet_id: i32,
+        the_names: &[String],
+    ) -> Result<(), ModelError> {
+        use crate::schema::snippet_tags::dsl::{snippet_id, snippet_tags, tag_id};
+        use diesel::prelude::*;
+
+        conn.transaction::<(), ModelError, _>(|| {
+            Self::remove_all_from_snippet(conn, the_snippet_id)?;
+
+            for the_name in normalize_names(the_names) {
+                let tag = Self::find_or_create(conn, &the_name)?;
+                diesel::insert_into(snippet_tags)
+                    .values((snippet_id.eq(the_snippet_id), tag_id.eq(tag.id)))
+                    .execute(conn)?;
+            }
+            SnippetRevision::record_tags(conn, the_snippet_id)?;
+
+            Ok(())
+        })
+    }
+
+    /// Takes every tag off of a snippet. The tags themselves are left alone.
+    pub fn remove_all_from_snippet(
+        conn: &DbConn,
+        the_snippet_id: i32,
+    ) -> Result<usize, ModelError> {
+        use crate::schema::snippet_tags::dsl::{snippet_id, snippet_tags};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(snippet_tags.filter(snippet_id.eq(the_snippet_id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+/// Trims and lowercases a tag name.
+pub fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Trims and lowercases tag names, dropping blanks and duplicates.
+pub fn normalize_names(names: &[String]) -> Vec<String> {
+    let mut normalized = names
+        .iter()
+        .map(|name| normalize_name(name))
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>();
+    normalized.sort();
+    normalized.dedup();
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
+
+    #[test]
+    fn test_find_or_create() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let tag = Tag::find_or_create(&conn, " Rust ").unwrap();
+        assert_eq!(tag.id, Tag::find_or_create(&conn, "rust").unwrap().id);
+        assert_eq!(tag.id, Tag::find_or_create(&conn, "RUST").unwrap().id);
+        assert_eq!(
+            tag.id,
+            Tag::find_by_name(&conn, "rust").unwrap().unwrap().id
+        );
+        assert!(Tag::find_by_name(&conn, " Rust ").unwrap().is_none());
+
+        let other = Tag::find_or_create(&conn, "rendering").unwrap();
+        assert_ne!(tag.id, other.id);
+    }
+
+    #[test]
+    fn test_set_for_snippet() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let snippet = create_test_snippet(&conn, "https://example.com/");
+        let names = vec![
+            "Rust".to_string(),
+            " rust".to_string(),
+            "".to_string(),
+            "Rendering".to_string(),
+        ];
+        Tag::set_for_snippet(&conn, snippet.id, &names).unwrap();
+        assert_eq!(
+            vec!["rendering", "rust"],
+            Tag::find_names_by_snippet_id(&conn, snippet.id).unwrap()
+        );
+
+        Tag::set_for_snippet(&conn, snippet.id, &["metal".to_string()]).unwrap();
+        assert_eq!(
+            vec!["metal"],
+            Tag::find_names_by_snippet_id(&conn, snippet.id).unwrap()
+        );
+
+        Tag::remove_all_from_snippet(&conn, snippet.id).unwrap();
+        assert!(Tag::find_names_by_snippet_id(&conn, snippet.id)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use super::{last_insert_rowid, r_to_opt, ModelError};
+use crate::db::DbConn;
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A section of the site which snippets are filed under, such as "links" or
+/// "udevgames". Every snippet belongs to exactly one taxonomy, referenced by
+/// its slug.
+#[derive(Debug, Deserialize, Queryable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Taxonomy {
+    pub id: i32,
+
+    /// The short name used in URLs and stored on each snippet. This never
+    /// changes once the taxonomy is created.
+    pub slug: String,
+
+    /// The human-readable name of this taxonomy.
+    pub display_name: String,
+
+    /// A few words on what goes in this taxonomy.
+    pub description: String,
+
+    /// Taxonomies are listed in ascending order of this.
+    pub sort_order: i32,
+
+    /// The icon a new snippet in this taxonomy gets when none is picked.
+    pub default_icon: String,
+
+    /// Whether this taxonomy is hidden from everyone but admins.
+    pub hidden: bool,
+
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Taxonomy {
+    pub fn create(
+        conn: &DbConn,
+        the_slug: &str,
+        the_display_name: &str,
+        the_description: &str,
+        the_sort_order: i32,
+        the_default_icon: &str,
+        is_hidden: bool,
+    ) -> Result<Self, ModelError> {
+        use crate::schema::taxonomies::dsl::{
+            created_at, default_icon, description, display_name, hidden, id, slug, sort_order,
+            taxonomies, updated_at,
+        };
+        use diesel::prelude::*;
+
+        let taxonomy = conn.transaction::<Self, ModelError, _>(|| {
+            let inserted_at = Utc::now().naive_utc();
+
+            diesel::insert_into(taxonomies)
+                .values((
+                    slug.eq(the_slug),
+                    display_name.eq(the_display_name),
+                    description.eq(the_description),
+                    sort_order.eq(the_sort_order),
+                    default_icon.eq(the_default_icon),
+                    hidden.eq(is_hidden),
+                    created_at.eq(&inserted_at),
+                    updated_at.eq(&inserted_at),
+                ))
+                .execute(conn)?;
+
+            let rowid = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+
+            Ok(taxonomies.filter(id.eq(rowid)).first::<Self>(conn)?)
+        })?;
+
+        Ok(taxonomy)
+    }
+
+    /// Finds a taxonomy by its slug.
+    pub fn find_by_slug(conn: &DbConn, the_slug: &str) -> Result<Option<Self>, ModelError> {
+        use crate::schema::taxonomies::dsl::{slug, taxonomies};
+        use diesel::prelude::*;
+
+        let taxonomy = taxonomies.filter(slug.eq(the_slug)).first::<Self>(conn);
+
+        r_to_opt(taxonomy)
+    }
+
+    /// All of the taxonomies, in the order they should be shown.
+    pub fn find_all(conn: &DbConn, visible_only: bool) -> Result<Vec<Self>, ModelError> {
+        use crate::schema::taxonomies::dsl::{display_name, hidden, sort_order, taxonomies};
+        use diesel::prelude::*;
+
+        let q = taxonomies.order((sort_order.asc(), display_name.asc()));
+
+        let r = if visible_only {
+            q.filter(hidden.eq(false)).load::<Self>(conn)?
+        } else {
+            q.load::<Self>(conn)?
+        };
+
+        Ok(r)
+    }
+
+    /// How many snippets are filed under each taxonomy, keyed by slug.
+    /// Taxonomies with no snippets are absent from the map, and snippets in the
+    /// trash aren't counted.
+    pub fn count_snippets(
+        conn: &DbConn,
+        visible_only: bool,
+    ) -> Result<HashMap<String, i64>, ModelError> {
+        use crate::schema::snippets::dsl::{deleted_at, hidden, publish_at, snippets, taxonomy};
+        use diesel::{dsl::sql, prelude::*, sql_types::BigInt};
+
+        // diesel won't mix an aggregate like count_star with a plain column
+        // in one select, even when grouping by that column, so the count has
+        // to be spelled out by hand.
+        let q = snippets
+            .filter(deleted_at.is_null())
+            .group_by(taxonomy)
+            .select((taxonomy, sql::<BigInt>("COUNT(*)")));
+
+        let r = if visible_only {
+            q.filter(hidden.eq(false))
+                .filter(
+                    publish_at
+                        .is_null()
+                        .or(publish_at.le(Utc::now().naive_utc())),
+                )
+                .load::<(String, i64)>(conn)?
+        } else {
+            q.load::<(String, i64)>(conn)?
+        };
+
+        Ok(r.into_iter().collect())
+    }
+
+    /// Saves everything but the slug, which is fixed once the taxonomy has
+    /// been created.
+    pub fn update(&self, conn: &DbConn) -> Result<(), ModelError> {
+        use crate::schema::taxonomies::dsl::{
+            default_icon, description, display_name, hidden, sort_order, taxonomies, updated_at,
+        };
+        use diesel::prelude::*;
+
+        diesel::update(taxonomies.find(self.id))
+            .set((
+                display_name.eq(&self.display_name),
+                description.eq(&self.description),
+                sort_order.eq(self.sort_order),
+                default_icon.eq(&self.default_icon),
+                hidden.eq(self.hidden),
+                updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    pub fn delete(&self, conn: &DbConn) -> Result<usize, ModelError> {
+        use crate::schema::taxonomies::dsl::{id, taxonomies};
+        use diesel::prelude::*;
+
+        let r = diesel::delete(taxonomies.filter(id.eq(self.id))).execute(conn)?;
+
+        Ok(r)
+    }
+}
+
+/// Whether a string is usable as a taxonomy slug: lowercase letters, digits,
+/// and dashes, because it ends up in URLs.
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, models::snippets::create_test_snippet};
+
+    #[test]
+    fn test_taxonomies() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        let mut taxonomy =
+            Taxonomy::create(&conn, "demos", "Demos", "", 2, "safari.png", true).unwrap();
+        assert_eq!(
+            taxonomy.id,
+            Taxonomy::find_by_slug(&conn, "demos").unwrap().unwrap().id
+        );
+        assert!(Taxonomy::find_by_slug(&conn, "nope").unwrap().is_none());
+
+        let slugs = |visible_only| {
+            Taxonomy::find_all(&conn, visible_only)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.slug)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["udevgames", "links", "demos"], slugs(false));
+        assert_eq!(vec!["udevgames", "links"], slugs(true));
+
+        taxonomy.hidden = false;
+        taxonomy.sort_order = -1;
+        taxonomy.update(&conn).unwrap();
+        assert_eq!(vec!["demos", "udevgames", "links"], slugs(true));
+
+        create_test_snippet(&conn, "https://example.com/a");
+        create_test_snippet(&conn, "https://example.com/b");
+        let counts = Taxonomy::count_snippets(&conn, false).unwrap();
+        assert_eq!(Some(&2), counts.get("links"));
+        assert_eq!(None, counts.get("demos"));
+
+        taxonomy.delete(&conn).unwrap();
+        assert!(Taxonomy::find_by_slug(&conn, "demos").unwrap().is_none());
+
+        assert!(is_valid_slug("udevgames-2"));
+        assert!(!is_valid_slug("Links"));
+        assert!(!is_valid_slug("a/b"));
+        assert!(!is_valid_slug(""));
+    }
+}
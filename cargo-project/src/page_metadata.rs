@@ -0,0 +1,411 @@
+//! Reads the metadata a web page declares about itself, such as its Open
+//! Graph and Twitter card tags, so that sharing a link doesn't mean retyping
+//! what the page already says about itself.
+
+use reqwest::{
+    header::{CONTENT_TYPE, LOCATION},
+    redirect, Url,
+};
+use rocket::tokio::net::lookup_host;
+use scraper::{Html, Selector};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+use thiserror::Error;
+
+/// How long to wait on a page before giving up on it.
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The most of a page to read. Everything we're after is in the head, which
+/// comes first, so there's no need to download an entire video.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// The most redirects to follow before giving up on a page.
+const MAX_REDIRECTS: usize = 10;
+
+/// What a page says about itself. Anything the page doesn't declare is left
+/// as `None`.
+#[derive(Debug, Default, PartialEq)]
+pub struct PageMetadata {
+    /// The page's title, preferring what it tells social sites over its
+    /// `<title>`, which is often cluttered with the site's name.
+    pub title: Option<String>,
+
+    /// A sentence or two about the page.
+    pub summary: Option<String>,
+
+    /// The URL the page would rather be known by.
+    pub canonical_url: Option<String>,
+
+    /// Where the page's icon is, if it says.
+    pub icon_url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PageMetadataError {
+    #[error("Only http and https links can be fetched")]
+    UnsupportedScheme,
+
+    #[error("Couldn't fetch the page with error {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("The file is bigger than {0} bytes")]
+    TooBig(usize),
+
+    #[error("The icon isn't on the page's own site")]
+    ForeignIcon,
+
+    #[error("Followed too many redirects")]
+    TooManyRedirects,
+
+    #[error("{0} isn't a public address")]
+    NonPublicHost(String),
+
+    #[error("Couldn't look up the host with error {0}")]
+    LookupError(#[from] std::io::Error),
+}
+
+/// Fetches pages to read their metadata. Unlike checking links, this follows
+/// redirects, since it's the page at the end that counts. The links come from
+/// users, so only hosts out on the internet are ever connected to, lest the
+/// server be talked into fetching pages off of its own network.
+#[derive(Clone, Default)]
+pub struct PageMetadataClient;
+
+impl PageMetadataClient {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Fetches a page and reads its metadata. Pages which aren't HTML have
+    /// no metadata to speak of, but still have a canonical URL: wherever
+    /// redirects ended up.
+    pub async fn fetch(&self, href: &str) -> Result<PageMetadata, PageMetadataError> {
+        let mut response = self.get(href).await?;
+        let final_url = response.url().clone();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok());
+
+        // a page with no content type at all is given the benefit of the doubt
+        if matches!(content_type, Some(content_type) if !content_type.contains("html")) {
+            return Ok(PageMetadata {
+                canonical_url: Some(final_url.to_string()),
+                ..PageMetadata::default()
+            });
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() >= MAX_BODY_BYTES {
+                break;
+            }
+        }
+
+        Ok(parse(&String::from_utf8_lossy(&body), &final_url))
+    }
+
+    /// Downloads the icon a page declares, or its site's `/favicon.ico` if
+    /// it doesn't declare one. Icons bigger than `max_bytes` are refused
+    /// rather than cut short, as are icons anywhere but the page's own
+    /// site.
+    pub async fn fetch_favicon(
+        &self,
+        href: &str,
+        max_bytes: usize,
+    ) -> Result<Vec<u8>, PageMetadataError> {
+        let page_url = Url::parse(href).map_err(|_| PageMetadataError::UnsupportedScheme)?;
+        let metadata = self.fetch(href).await?;
+        let icon_url = match metadata.icon_url {
+            Some(icon_url) => Url::parse(&icon_url),
+            None => page_url.join("/favicon.ico"),
+        }
+        .map_err(|_| PageMetadataError::UnsupportedScheme)?;
+        if !is_same_site(&page_url, &icon_url) {
+            return Err(PageMetadataError::ForeignIcon);
+        }
+
+        // icons are only fetched from where they say they are, since a
+        // redirect could take us anywhere
+        let mut response = get_once(&icon_url).await?.error_for_status()?;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() > max_bytes {
+                return Err(PageMetadataError::TooBig(max_bytes));
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Fetches a page, following its redirects. Each hop is made with
+    /// `get_once`, so a public page can't send us anywhere that isn't.
+    async fn get(&self, href: &str) -> Result<reqwest::Response, PageMetadataError> {
+        let mut url = Url::parse(href).map_err(|_| PageMetadataError::UnsupportedScheme)?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            let response = get_once(&url).await?;
+            let location = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok());
+
+            match location {
+                Some(location) if response.status().is_redirection() => {
+                    url = url
+                        .join(location)
+                        .map_err(|_| PageMetadataError::UnsupportedScheme)?;
+                }
+                _ => return Ok(response.error_for_status()?),
+            }
+        }
+
+        Err(PageMetadataError::TooManyRedirects)
+    }
+}
+
+/// Makes a single request, without following any redirect. The host is looked
+/// up once, checked to be public, and then connected to at exactly the
+/// addresses which were checked, so that it can't give a different answer
+/// when it's asked a second time.
+async fn get_once(url: &Url) -> Result<reqwest::Response, PageMetadataError> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(PageMetadataError::UnsupportedScheme);
+    }
+    let addresses = require_public_host(url).await?;
+
+    let mut builder = reqwest::ClientBuilder::new()
+        .user_agent("Rust/reqwest/iDevGames.com")
+        .timeout(TIMEOUT)
+        .redirect(redirect::Policy::none());
+    // hosts which are addresses already aren't looked up at all
+    if let Some(domain) = url.domain() {
+        builder = builder.resolve_to_addrs(domain, &addresses);
+    }
+
+    Ok(builder.build()?.get(url.clone()).send().await?)
+}
+
+/// Whether two URLs are on the same host, counting a `www.` subdomain as the
+/// host itself.
+fn is_same_site(a: &Url, b: &Url) -> bool {
+    let host = |url: &Url| {
+        url.host_str()
+            .map(|host| host.to_lowercase().trim_start_matches("www.").to_owned())
+    };
+
+    host(a).is_some() && host(a) == host(b)
+}
+
+/// Makes sure that every address a URL's host has is out on the internet,
+/// rather than on this machine or its network, and gives back those
+/// addresses.
+async fn require_public_host(url: &Url) -> Result<Vec<SocketAddr>, PageMetadataError> {
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addresses = match host
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+    {
+        Ok(address) => vec![SocketAddr::new(address, port)],
+        Err(_) => lookup_host((host, port)).await?.collect(),
+    };
+
+    if !addresses.is_empty() && addresses.iter().all(|address| is_public(address.ip())) {
+        Ok(addresses)
+    } else {
+        Err(PageMetadataError::NonPublicHost(host.to_owned()))
+    }
+}
+
+fn is_public(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_public_v4(address),
+        IpAddr::V6(address) => {
+            let segments = address.segments();
+            match segments {
+                // an IPv4 address in disguise
+                [0, 0, 0, 0, 0, 0xffff, high, low] => is_public_v4(Ipv4Addr::new(
+                    (high >> 8) as u8,
+                    high as u8,
+                    (low >> 8) as u8,
+                    low as u8,
+                )),
+                _ => {
+                    !address.is_loopback()
+                        && !address.is_unspecified()
+                        // unique local, fc00::/7
+                        && segments[0] & 0xfe00 != 0xfc00
+                        // link local, fe80::/10
+                        && segments[0] & 0xffc0 != 0xfe80
+                }
+            }
+        }
+    }
+}
+
+fn is_public_v4(address: Ipv4Addr) -> bool {
+    let octets = address.octets();
+
+    !address.is_private()
+        && !address.is_loopback()
+        && !address.is_link_local()
+        && !address.is_unspecified()
+        && !address.is_broadcast()
+        && !address.is_documentation()
+        && octets[0] != 0
+        // shared address space, 100.64.0.0/10
+        && !(octets[0] == 100 && octets[1] & 0xc0 == 64)
+}
+
+/// Reads the metadata out of a page which was found at the given URL, which
+/// is used to make relative URLs absolute.
+pub fn parse(html: &str, url: &Url) -> PageMetadata {
+    let document = Html::parse_document(html);
+
+    let first_of = |selectors: &[&str], attr: Option<&str>| -> Option<String> {
+        selectors.iter().find_map(|selector| {
+            let selector = Selector::parse(selector).unwrap();
+            document.select(&selector).find_map(|element| {
+                let text = match attr {
+                    Some(attr) => element.value().attr(attr)?.to_owned(),
+                    None => element.text().collect::<String>(),
+                };
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                Some(text).filter(|text| !text.is_empty())
+            })
+        })
+    };
+
+    let title = first_of(
+        &[
+            r#"meta[property="og:title"]"#,
+            r#"meta[name="twitter:title"]"#,
+            r#"meta[property="twitter:title"]"#,
+        ],
+        Some("content"),
+    )
+    .or_else(|| first_of(&["title"], None));
+
+    let summary = first_of(
+        &[
+            r#"meta[property="og:description"]"#,
+            r#"meta[name="twitter:description"]"#,
+            r#"meta[property="twitter:description"]"#,
+            r#"meta[name="description"]"#,
+        ],
+        Some("content"),
+    );
+
+    let canonical_url = first_of(&[r#"link[rel="canonical"]"#], Some("href"))
+        .or_else(|| first_of(&[r#"meta[property="og:url"]"#], Some("content")))
+        .and_then(|canonical| url.join(&canonical).ok())
+        .unwrap_or_else(|| url.clone());
+
+    // matches both "icon" and the older "shortcut icon"
+    let icon_url = first_of(&[r#"link[rel~="icon"]"#], Some("href"))
+        .and_then(|icon| url.join(&icon).ok())
+        .map(|icon| icon.to_string());
+
+    PageMetadata {
+        title,
+        summary,
+        canonical_url: Some(canonical_url.to_string()),
+        icon_url,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let url = Url::parse("https://example.com/posts/1?utm_source=discord").unwrap();
+
+        let html = r#"<html><head>
+            <title>  A Post
+                | Example Blog </title>
+            <meta name="description" content="The plain description">
+            <meta name="twitter:description" content="The twitter description">
+            <link rel="canonical" href="/posts/1">
+            <link rel="shortcut icon" href="/static/icon.png">
+        </head><body></body></html>"#;
+        assert_eq!(
+            PageMetadata {
+                title: Some("A Post | Example Blog".to_owned()),
+                summary: Some("The twitter description".to_owned()),
+                canonical_url: Some("https://example.com/posts/1".to_owned()),
+                icon_url: Some("https://example.com/static/icon.png".to_owned()),
+            },
+            parse(html, &url)
+        );
+
+        let html = r#"<html><head>
+            <title>A Post | Example Blog</title>
+            <meta property="og:title" content="A Post">
+            <meta property="og:description" content="">
+            <meta name="description" content="The plain description">
+        </head><body></body></html>"#;
+        assert_eq!(
+            PageMetadata {
+                title: Some("A Post".to_owned()),
+                summary: Some("The plain description".to_owned()),
+                canonical_url: Some(url.to_string()),
+                icon_url: None,
+            },
+            parse(html, &url)
+        );
+    }
+
+    #[test]
+    fn test_is_public() {
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:2800:220:1::".parse().unwrap()));
+        assert!(!is_public("127.0.0.1".parse().unwrap()));
+        assert!(!is_public("10.1.2.3".parse().unwrap()));
+        assert!(!is_public("192.168.0.1".parse().unwrap()));
+        assert!(!is_public("169.254.169.254".parse().unwrap()));
+        assert!(!is_public("100.64.0.1".parse().unwrap()));
+        assert!(!is_public("0.0.0.0".parse().unwrap()));
+        assert!(!is_public("::1".parse().unwrap()));
+        assert!(!is_public("fd00::1".parse().unwrap()));
+        assert!(!is_public("fe80::1".parse().unwrap()));
+        assert!(!is_public("::ffff:127.0.0.1".parse().unwrap()));
+
+        let page = Url::parse("https://www.example.com/posts/1").unwrap();
+        assert!(is_same_site(
+            &page,
+            &Url::parse("https://example.com/icon.png").unwrap()
+        ));
+        assert!(!is_same_site(
+            &page,
+            &Url::parse("http://127.0.0.1:8000/icon.png").unwrap()
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn test_fetch_refuses_local_hosts() {
+        let client = PageMetadataClient::new();
+
+        for href in &[
+            "http://127.0.0.1/",
+            "http://[::1]:8000/",
+            "http://localhost/",
+            "http://169.254.169.254/latest/meta-data/",
+        ] {
+            assert!(matches!(
+                client.fetch(href).await,
+                Err(PageMetadataError::NonPublicHost(_))
+            ));
+        }
+        assert!(matches!(
+            client.fetch("file:///etc/passwd").await,
+            Err(PageMetadataError::UnsupportedScheme)
+        ));
+    }
+}
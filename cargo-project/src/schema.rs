@@ -1,3 +1,25 @@
+table! {
+    bookmarks (id) {
+        id -> Integer,
+        snippet_id -> Integer,
+        user_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    comments (id) {
+        id -> Integer,
+        snippet_id -> Integer,
+        parent_id -> Nullable<Integer>,
+        author_id -> Integer,
+        body -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     github_user_records (id) {
         id -> BigInt,
@@ -8,6 +30,37 @@ table! {
     }
 }
 
+table! {
+    icon_rules (id) {
+        id -> Integer,
+        pattern -> Text,
+        icon -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    icons (id) {
+        id -> Integer,
+        name -> Text,
+        filename -> Text,
+        content_type -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    link_checks (id) {
+        id -> Integer,
+        snippet_id -> Integer,
+        status_code -> Nullable<Integer>,
+        redirect_to -> Nullable<Text>,
+        error -> Nullable<Text>,
+        consecutive_failures -> Integer,
+        checked_at -> Timestamp,
+    }
+}
+
 table! {
     permissions (id) {
         id -> Integer,
@@ -16,6 +69,50 @@ table! {
     }
 }
 
+table! {
+    snippet_clicks (snippet_id, day) {
+        snippet_id -> Integer,
+        day -> Date,
+        clicks -> Integer,
+    }
+}
+
+table! {
+    snippet_revisions (id) {
+        id -> Integer,
+        snippet_id -> Integer,
+        editor_id -> Nullable<Integer>,
+        taxonomy -> Text,
+        hidden -> Bool,
+        title -> Text,
+        icon -> Text,
+        shared_by -> Text,
+        shared_on -> Timestamp,
+        summary -> Text,
+        description -> Text,
+        href -> Text,
+        created_at -> Timestamp,
+        tags -> Nullable<Text>,
+        publish_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    snippet_tags (snippet_id, tag_id) {
+        snippet_id -> Integer,
+        tag_id -> Integer,
+    }
+}
+
+table! {
+    snippet_votes (id) {
+        id -> Integer,
+        snippet_id -> Integer,
+        user_id -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     snippets (id) {
         id -> Integer,
@@ -31,6 +128,52 @@ table! {
         href -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        deleted_at -> Nullable<Timestamp>,
+        publish_at -> Nullable<Timestamp>,
+        canonical_href -> Text,
+        vote_count -> Integer,
+    }
+}
+
+table! {
+    submissions (id) {
+        id -> Integer,
+        submitter_id -> Integer,
+        taxonomy -> Text,
+        title -> Text,
+        shared_by -> Text,
+        summary -> Text,
+        description -> Text,
+        href -> Text,
+        canonical_href -> Text,
+        status -> Text,
+        rejection_reason -> Nullable<Text>,
+        snippet_id -> Nullable<Integer>,
+        reviewer_id -> Nullable<Integer>,
+        reviewed_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
+table! {
+    taxonomies (id) {
+        id -> Integer,
+        slug -> Text,
+        display_name -> Text,
+        description -> Text,
+        sort_order -> Integer,
+        default_icon -> Text,
+        hidden -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -41,4 +184,31 @@ table! {
     }
 }
 
-allow_tables_to_appear_in_same_query!(github_user_records, permissions, snippets, users,);
+joinable!(bookmarks -> snippets (snippet_id));
+joinable!(comments -> snippets (snippet_id));
+joinable!(link_checks -> snippets (snippet_id));
+joinable!(snippet_clicks -> snippets (snippet_id));
+joinable!(snippet_revisions -> snippets (snippet_id));
+joinable!(snippet_tags -> snippets (snippet_id));
+joinable!(snippet_tags -> tags (tag_id));
+joinable!(snippet_votes -> snippets (snippet_id));
+joinable!(submissions -> snippets (snippet_id));
+
+allow_tables_to_appear_in_same_query!(
+    bookmarks,
+    comments,
+    github_user_records,
+    icon_rules,
+    icons,
+    link_checks,
+    permissions,
+    snippet_clicks,
+    snippet_revisions,
+    snippet_tags,
+    snippet_votes,
+    snippets,
+    submissions,
+    tags,
+    taxonomies,
+    users,
+);
@@ -0,0 +1,373 @@
+//! Moves snippets in and out of the database in bulk, for carrying curated
+//! links between environments or keeping a backup. Snippets are written as
+//! JSON Lines or CSV, either way with the same camelCase fields as the
+//! `Snippet` the API hands out.
+
+use crate::{
+    db::DbConn,
+    models::{Icon, ModelError, Snippet, Taxonomy},
+};
+use chrono::NaiveDateTime;
+use diesel::{result::Error as DieselError, Connection};
+use serde::Deserialize;
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// The file formats snippets can be moved around in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransferFormat {
+    /// One JSON object per line.
+    JsonLines,
+
+    /// A header row of field names, then one snippet per row.
+    Csv,
+}
+
+impl TransferFormat {
+    /// Guesses the format from a file's extension, which is JSON Lines
+    /// unless it's `.csv`.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            TransferFormat::Csv
+        } else {
+            TransferFormat::JsonLines
+        }
+    }
+}
+
+impl FromStr for TransferFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(TransferFormat::JsonLines),
+            "csv" => Ok(TransferFormat::Csv),
+            _ => Err(format!("Unknown format {}, expected jsonl or csv", s)),
+        }
+    }
+}
+
+/// A snippet as read from a file. This is the same shape as `Snippet`, but
+/// forgiving of what a person writing one by hand would leave out: ids,
+/// timestamps, and anything else with an obvious default.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetRecord {
+    #[serde(default)]
+    pub creator_id: Option<i32>,
+
+    pub taxonomy: String,
+    #[serde(default)]
+    pub hidden: bool,
+
+    pub title: String,
+    /// Left out, this is the snippet's icon as it is or, for a new snippet,
+    /// its taxonomy's default icon.
+    #[serde(default)]
+    pub icon: Option<String>,
+    pub shared_by: String,
+    pub shared_on: NaiveDateTime,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub description: String,
+    pub href: String,
+
+    #[serde(default)]
+    pub publish_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Error)]
+pub enum TransferError {
+    #[error("Couldn't read or write with error {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Couldn't read the JSON with error {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("Couldn't read the CSV with error {0}")]
+    CsvError(#[from] csv::Error),
+
+    #[error("{0}")]
+    ModelError(#[from] ModelError),
+
+    #[error("No such taxonomy {0} exists")]
+    NoSuchTaxonomy(String),
+
+    #[error("No such icon {0} exists")]
+    NoSuchIcon(String),
+
+    #[error("No creator id was given for a new snippet")]
+    NoCreator,
+}
+
+/// A row read from a file: the line it started on, and what was on it.
+pub type ImportRow = (u64, Result<SnippetRecord, TransferError>);
+
+/// How importing a file went.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// How many snippets were new.
+    pub created: usize,
+
+    /// How many snippets were already there, going by their hrefs, and were
+    /// updated to match the file.
+    pub updated: usize,
+
+    /// The rows which couldn't be imported, by line number, and why.
+    pub failed: Vec<(u64, String)>,
+}
+
+/// Writes snippets out in the given format.
+pub fn export<W: Write>(
+    snippets: &[Snippet],
+    format: TransferFormat,
+    mut writer: W,
+) -> Result<(), TransferError> {
+    match format {
+        TransferFormat::JsonLines => {
+            for snippet in snippets {
+                serde_json::to_writer(&mut writer, snippet)?;
+                writeln!(writer)?;
+            }
+        }
+        TransferFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            for snippet in snippets {
+                csv_writer.serialize(snippet)?;
+            }
+            csv_writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads every row of a file, along with the line it started on. A row which
+/// can't be read doesn't stop the rest from being read.
+pub fn read_records<R: Read>(
+    format: TransferFormat,
+    reader: R,
+) -> Result<Vec<ImportRow>, TransferError> {
+    let mut rows = Vec::new();
+
+    match format {
+        TransferFormat::JsonLines => {
+            for (i, line) in BufReader::new(reader).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                rows.push((
+                    i as u64 + 1,
+                    serde_json::from_str(&line).map_err(Into::into),
+                ));
+            }
+        }
+        TransferFormat::Csv => {
+            let mut csv_reader = csv::Reader::from_reader(reader);
+            let headers = csv_reader.headers()?.clone();
+            let mut record = csv::StringRecord::new();
+            loop {
+                let line = csv_reader.position().line();
+                match csv_reader.read_record(&mut record) {
+                    Ok(false) => break,
+                    Ok(true) => {
+                        rows.push((line, record.deserialize(Some(&headers)).map_err(Into::into)))
+                    }
+                    // there's no reading on past a broken file
+                    Err(e) if e.is_io_error() => return Err(e.into()),
+                    Err(e) => rows.push((line, Err(e.into()))),
+                }
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Saves the rows read from a file. Rows for a page which already has a
+/// snippet, going by canonical href, update that snippet; the rest become new
+/// snippets, credited to `creator_id` if given and otherwise to whoever the
+/// file says. With `dry_run`, everything is checked over as usual but then
+/// rolled back, so the summary says what would have happened.
+pub fn import(
+    conn: &DbConn,
+    rows: Vec<ImportRow>,
+    creator_id: Option<i32>,
+    dry_run: bool,
+) -> Result<ImportSummary, ModelError> {
+    let mut summary = ImportSummary::default();
+
+    let r = conn.transaction::<(), ModelError, _>(|| {
+        for (line, row) in rows {
+            match row.and_then(|record| import_record(conn, &record, creator_id)) {
+                Ok(true) => summary.created += 1,
+                Ok(false) => summary.updated += 1,
+                Err(e) => summary.failed.push((line, e.to_string())),
+            }
+        }
+
+        if dry_run {
+            Err(DieselError::RollbackTransaction.into())
+        } else {
+            Ok(())
+        }
+    });
+
+    match r {
+        Err(ModelError::DieselError(DieselError::RollbackTransaction)) if dry_run => Ok(summary),
+        Err(e) => Err(e),
+        Ok(()) => Ok(summary),
+    }
+}
+
+/// Saves a single row, returning whether it made a new snippet.
+fn import_record(
+    conn: &DbConn,
+    record: &SnippetRecord,
+    creator_id: Option<i32>,
+) -> Result<bool, TransferError> {
+    let taxonomy = Taxonomy::find_by_slug(conn, &record.taxonomy)?
+        .ok_or_else(|| TransferError::NoSuchTaxonomy(record.taxonomy.clone()))?;
+    // a CSV has every column on every row, so an empty one means left out
+    let icon = record
+        .icon
+        .as_deref()
+        .map(str::trim)
+        .filter(|icon| !icon.is_empty());
+    if let Some(icon) = icon {
+        if !Icon::is_registered(conn, icon)? {
+            return Err(TransferError::NoSuchIcon(icon.to_owned()));
+        }
+    }
+
+    match Snippet::find_by_canonical_href(conn, &record.href)? {
+        Some(mut snippet) => {
+            snippet.taxonomy = record.taxonomy.clone();
+            snippet.hidden = record.hidden;
+            snippet.title = record.title.clone();
+            if let Some(icon) = icon {
+                snippet.icon = icon.to_owned();
+            }
+            snippet.shared_by = record.shared_by.clone();
+            snippet.shared_on = record.shared_on;
+            snippet.summary = record.summary.clone();
+            snippet.description = record.description.clone();
+            snippet.href = record.href.clone();
+            snippet.publish_at = record.publish_at;
+            snippet.update(conn, None)?;
+
+            Ok(false)
+        }
+        None => {
+            let the_creator_id = creator_id
+                .or(record.creator_id)
+                .ok_or(TransferError::NoCreator)?;
+            Snippet::create(
+                conn,
+                the_creator_id,
+                &record.taxonomy,
+                record.hidden,
+                icon.unwrap_or(&taxonomy.default_icon),
+                &record.title,
+                &record.shared_by,
+                &record.shared_on,
+                &record.summary,
+                &record.description,
+                &record.href,
+                record.publish_at.as_ref(),
+            )?;
+
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_pool,
+        models::{snippets::create_test_snippet, SnippetFilter, SnippetSort, Visibility},
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let pool = test_pool();
+        let conn = pool.read().get().unwrap();
+
+        for href in &["https://example.com/a", "https://example.com/b"] {
+            let mut snippet = create_test_snippet(&conn, href);
+            snippet.title = "A link, with \"quotes\"".to_owned();
+            snippet.update(&conn, None).unwrap();
+        }
+        let everything = SnippetFilter {
+            visibility: Visibility::All,
+            ..SnippetFilter::default()
+        };
+        let snippets =
+            Snippet::find_filtered(&conn, &everything, SnippetSort::SharedOn, 0, 10).unwrap();
+
+        for format in &[TransferFormat::JsonLines, TransferFormat::Csv] {
+            let mut file = Vec::new();
+            export(&snippets, *format, &mut file).unwrap();
+
+            // everything in the file is already there, so nothing is new
+            let rows = read_records(*format, file.as_slice()).unwrap();
+            assert_eq!(2, rows.len());
+            let summary = import(&conn, rows, None, true).unwrap();
+            assert_eq!((0, 2), (summary.created, summary.updated));
+            assert!(summary.failed.is_empty());
+        }
+
+        let file = concat!(
+            r#"{"taxonomy":"links","title":"New","sharedBy":"someone","sharedOn":"2021-01-01T00:00:00","href":"https://example.com/c"}"#,
+            "\n\n",
+            r#"{"taxonomy":"nowhere","title":"Lost","sharedBy":"someone","sharedOn":"2021-01-01T00:00:00","href":"https://example.com/d"}"#,
+            "\n",
+            "not json\n",
+        );
+        let rows = read_records(TransferFormat::JsonLines, file.as_bytes()).unwrap();
+
+        let summary = import(&conn, rows, Some(1), true).unwrap();
+        assert_eq!(1, summary.created);
+        assert_eq!(
+            vec![3, 4],
+            summary.failed.iter().map(|f| f.0).collect::<Vec<_>>()
+        );
+        assert_eq!(2, Snippet::count(&conn, &everything).unwrap());
+
+        let rows = read_records(TransferFormat::JsonLines, file.as_bytes()).unwrap();
+        import(&conn, rows, Some(1), false).unwrap();
+        assert_eq!(3, Snippet::count(&conn, &everything).unwrap());
+        let new = Snippet::find_by_canonical_href(&conn, "https://example.com/c")
+            .unwrap()
+            .unwrap();
+        let links = Taxonomy::find_by_slug(&conn, "links").unwrap().unwrap();
+        assert_eq!(links.default_icon, new.icon);
+
+        // an update without an icon keeps the one it has, and an icon which
+        // isn't registered is turned away
+        let file = concat!(
+            r#"{"taxonomy":"links","title":"A","sharedBy":"someone","sharedOn":"2021-01-01T00:00:00","href":"https://example.com/a"}"#,
+            "\n",
+            r#"{"taxonomy":"links","title":"B","icon":"nope.png","sharedBy":"someone","sharedOn":"2021-01-01T00:00:00","href":"https://example.com/b"}"#,
+            "\n",
+        );
+        let rows = read_records(TransferFormat::JsonLines, file.as_bytes()).unwrap();
+        let summary = import(&conn, rows, Some(1), false).unwrap();
+        assert_eq!(1, summary.updated);
+        assert_eq!(
+            vec![2],
+            summary.failed.iter().map(|f| f.0).collect::<Vec<_>>()
+        );
+        let a = Snippet::find_by_canonical_href(&conn, "https://example.com/a")
+            .unwrap()
+            .unwrap();
+        assert_eq!(("A", "safari.png"), (a.title.as_str(), a.icon.as_str()));
+    }
+}
@@ -26,6 +26,16 @@ docker run --rm --name idevgamesc \
 
 pushd react-project
   npm run-script build
+
+  # precompress everything worth compressing, for whatever serves the build
+  find build -type f \( -name '*.js' -o -name '*.css' -o -name '*.html' \
+    -o -name '*.svg' -o -name '*.json' -o -name '*.txt' \) \
+    -exec gzip -9 -k -f {} \;
+  if command -v brotli > /dev/null; then
+    find build -type f \( -name '*.js' -o -name '*.css' -o -name '*.html' \
+      -o -name '*.svg' -o -name '*.json' -o -name '*.txt' \) \
+      -exec brotli -q 11 -k -f {} \;
+  fi
 popd
 
 # deploy the build if successful
@@ -0,0 +1,4 @@
+DROP TRIGGER snippets_fts_after_update;
+DROP TRIGGER snippets_fts_after_delete;
+DROP TRIGGER snippets_fts_after_insert;
+DROP TABLE snippets_fts;
@@ -0,0 +1,32 @@
+-- external-content full-text index over the searchable parts of a snippet.
+-- the snippets table remains the source of truth, the triggers below keep
+-- the index in step with it.
+CREATE VIRTUAL TABLE snippets_fts USING fts5(
+    title,
+    summary,
+    description,
+    shared_by,
+    href,
+    content='snippets',
+    content_rowid='id'
+);
+
+CREATE TRIGGER snippets_fts_after_insert AFTER INSERT ON snippets BEGIN
+    INSERT INTO snippets_fts(rowid, title, summary, description, shared_by, href)
+    VALUES (new.id, new.title, new.summary, new.description, new.shared_by, new.href);
+END;
+
+CREATE TRIGGER snippets_fts_after_delete AFTER DELETE ON snippets BEGIN
+    INSERT INTO snippets_fts(snippets_fts, rowid, title, summary, description, shared_by, href)
+    VALUES ('delete', old.id, old.title, old.summary, old.description, old.shared_by, old.href);
+END;
+
+CREATE TRIGGER snippets_fts_after_update AFTER UPDATE ON snippets BEGIN
+    INSERT INTO snippets_fts(snippets_fts, rowid, title, summary, description, shared_by, href)
+    VALUES ('delete', old.id, old.title, old.summary, old.description, old.shared_by, old.href);
+    INSERT INTO snippets_fts(rowid, title, summary, description, shared_by, href)
+    VALUES (new.id, new.title, new.summary, new.description, new.shared_by, new.href);
+END;
+
+-- index everything that was shared before this migration
+INSERT INTO snippets_fts(snippets_fts) VALUES ('rebuild');
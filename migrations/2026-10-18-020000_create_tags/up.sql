@@ -0,0 +1,12 @@
+CREATE TABLE tags(
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE snippet_tags(
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    tag_id INTEGER NOT NULL REFERENCES tags(id),
+    PRIMARY KEY (snippet_id, tag_id)
+);
+
+CREATE INDEX snippet_tags_tag_id ON snippet_tags(tag_id);
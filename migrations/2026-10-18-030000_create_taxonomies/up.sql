@@ -0,0 +1,26 @@
+CREATE TABLE taxonomies(
+    id INTEGER PRIMARY KEY NOT NULL,
+    slug TEXT NOT NULL UNIQUE,
+
+    display_name TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    default_icon TEXT NOT NULL,
+    hidden BOOLEAN NOT NULL DEFAULT false,
+
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+-- the two sections the site has always had
+INSERT INTO taxonomies(slug, display_name, sort_order, default_icon, created_at, updated_at)
+VALUES
+    ('udevgames', 'uDevGames', 0, 'safari.png', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP),
+    ('links', 'Links', 1, 'safari.png', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP);
+
+-- and anything else that snippets have been filed under, so that nothing is
+-- orphaned by this migration. these are hidden until someone gives them a
+-- proper name.
+INSERT OR IGNORE INTO taxonomies(slug, display_name, sort_order, default_icon, hidden, created_at, updated_at)
+SELECT DISTINCT taxonomy, taxonomy, 100, 'safari.png', true, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP
+FROM snippets;
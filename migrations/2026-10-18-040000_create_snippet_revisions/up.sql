@@ -0,0 +1,22 @@
+-- a full copy of a snippet as it stood after each edit.
+CREATE TABLE snippet_revisions(
+    id INTEGER PRIMARY KEY NOT NULL,
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    -- who made the edit, or null if it was made from the command line
+    editor_id INTEGER,
+
+    taxonomy TEXT NOT NULL,
+    hidden BOOLEAN NOT NULL,
+
+    title TEXT NOT NULL,
+    icon TEXT NOT NULL,
+    shared_by TEXT NOT NULL,
+    shared_on TIMESTAMP NOT NULL,
+    summary TEXT NOT NULL,
+    description TEXT NOT NULL,
+    href TEXT NOT NULL,
+
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX snippet_revisions_snippet_id ON snippet_revisions(snippet_id);
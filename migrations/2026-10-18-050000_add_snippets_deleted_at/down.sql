@@ -0,0 +1,6 @@
+DROP INDEX snippets_deleted_at;
+-- anything still in the trash would come back to life otherwise
+DELETE FROM snippet_tags WHERE snippet_id IN (SELECT id FROM snippets WHERE deleted_at IS NOT NULL);
+DELETE FROM snippet_revisions WHERE snippet_id IN (SELECT id FROM snippets WHERE deleted_at IS NOT NULL);
+DELETE FROM snippets WHERE deleted_at IS NOT NULL;
+ALTER TABLE snippets DROP COLUMN deleted_at;
@@ -0,0 +1,6 @@
+-- deleting a snippet only stamps it, so that it can be restored from the
+-- trash until it is purged for good. added in place rather than by copying
+-- the table so the full-text triggers on snippets survive.
+ALTER TABLE snippets ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX snippets_deleted_at ON snippets(deleted_at);
@@ -0,0 +1,2 @@
+DROP INDEX snippets_publish_at;
+ALTER TABLE snippets DROP COLUMN publish_at;
@@ -0,0 +1,5 @@
+-- a snippet with a publish_at in the future stays hidden from everyone but
+-- admins until that moment comes.
+ALTER TABLE snippets ADD COLUMN publish_at TIMESTAMP;
+
+CREATE INDEX snippets_publish_at ON snippets(publish_at);
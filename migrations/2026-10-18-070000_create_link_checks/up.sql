@@ -0,0 +1,16 @@
+-- the outcome of the most recent check of each snippet's href.
+CREATE TABLE link_checks(
+    id INTEGER PRIMARY KEY NOT NULL,
+    snippet_id INTEGER NOT NULL UNIQUE REFERENCES snippets(id),
+
+    -- null when no response came back at all, see error
+    status_code INTEGER,
+    -- where a 3xx response pointed
+    redirect_to TEXT,
+    -- why the request failed outright, such as a timeout or bad dns
+    error TEXT,
+
+    -- how many checks in a row have found the link dead, zero when alive
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    checked_at TIMESTAMP NOT NULL
+);
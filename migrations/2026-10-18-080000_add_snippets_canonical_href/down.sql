@@ -0,0 +1,2 @@
+DROP INDEX snippets_canonical_href;
+ALTER TABLE snippets DROP COLUMN canonical_href;
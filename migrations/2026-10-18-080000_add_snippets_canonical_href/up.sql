@@ -0,0 +1,8 @@
+-- the href with the noise taken out, so that the same page shared twice under
+-- slightly different URLs can be spotted. the rules for this live in the app,
+-- so existing rows start with their href as-is and `migrate` brings them up to
+-- date once the migrations have run.
+ALTER TABLE snippets ADD COLUMN canonical_href TEXT NOT NULL DEFAULT '';
+UPDATE snippets SET canonical_href = href;
+
+CREATE INDEX snippets_canonical_href ON snippets(canonical_href);
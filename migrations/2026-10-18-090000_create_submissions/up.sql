@@ -0,0 +1,31 @@
+-- links shared by anyone who's logged in, waiting on an admin to either turn
+-- them into snippets or turn them down.
+CREATE TABLE submissions(
+    id INTEGER PRIMARY KEY NOT NULL,
+    submitter_id INTEGER NOT NULL REFERENCES users(id),
+
+    taxonomy TEXT NOT NULL,
+    title TEXT NOT NULL,
+    -- who to credit, the submitter's github login when they submitted
+    shared_by TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    description TEXT NOT NULL,
+    href TEXT NOT NULL,
+    canonical_href TEXT NOT NULL,
+
+    -- one of pending, approved, or rejected
+    status TEXT NOT NULL DEFAULT 'pending',
+    -- why an admin turned it down
+    rejection_reason TEXT,
+    -- what it became once approved
+    snippet_id INTEGER REFERENCES snippets(id),
+    reviewer_id INTEGER REFERENCES users(id),
+    reviewed_at TIMESTAMP,
+
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX submissions_status_created_at ON submissions(status, created_at);
+CREATE INDEX submissions_submitter_id ON submissions(submitter_id);
+CREATE INDEX submissions_canonical_href ON submissions(canonical_href);
@@ -0,0 +1,13 @@
+DROP TRIGGER snippets_fts_after_update;
+CREATE TRIGGER snippets_fts_after_update AFTER UPDATE ON snippets BEGIN
+    INSERT INTO snippets_fts(snippets_fts, rowid, title, summary, description, shared_by, href)
+    VALUES ('delete', old.id, old.title, old.summary, old.description, old.shared_by, old.href);
+    INSERT INTO snippets_fts(rowid, title, summary, description, shared_by, href)
+    VALUES (new.id, new.title, new.summary, new.description, new.shared_by, new.href);
+END;
+
+DROP TRIGGER snippet_votes_after_delete;
+DROP TRIGGER snippet_votes_after_insert;
+DROP INDEX snippets_vote_count;
+ALTER TABLE snippets DROP COLUMN vote_count;
+DROP TABLE snippet_votes;
@@ -0,0 +1,35 @@
+-- one row for each user who voted a snippet up. taking a vote back deletes
+-- its row.
+CREATE TABLE snippet_votes(
+    id INTEGER PRIMARY KEY NOT NULL,
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE(snippet_id, user_id)
+);
+
+CREATE INDEX snippet_votes_user_id ON snippet_votes(user_id);
+
+-- kept in step with snippet_votes by the triggers below, so that sorting by
+-- votes doesn't mean counting them all on every request.
+ALTER TABLE snippets ADD COLUMN vote_count INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX snippets_vote_count ON snippets(vote_count);
+
+CREATE TRIGGER snippet_votes_after_insert AFTER INSERT ON snippet_votes BEGIN
+    UPDATE snippets SET vote_count = vote_count + 1 WHERE id = new.snippet_id;
+END;
+
+CREATE TRIGGER snippet_votes_after_delete AFTER DELETE ON snippet_votes BEGIN
+    UPDATE snippets SET vote_count = vote_count - 1 WHERE id = old.snippet_id;
+END;
+
+-- a vote changes nothing that's searched, so only reindex a snippet when
+-- something that is changes.
+DROP TRIGGER snippets_fts_after_update;
+CREATE TRIGGER snippets_fts_after_update
+AFTER UPDATE OF title, summary, description, shared_by, href ON snippets BEGIN
+    INSERT INTO snippets_fts(snippets_fts, rowid, title, summary, description, shared_by, href)
+    VALUES ('delete', old.id, old.title, old.summary, old.description, old.shared_by, old.href);
+    INSERT INTO snippets_fts(rowid, title, summary, description, shared_by, href)
+    VALUES (new.id, new.title, new.summary, new.description, new.shared_by, new.href);
+END;
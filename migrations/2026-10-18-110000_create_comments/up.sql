@@ -0,0 +1,16 @@
+-- discussion of a snippet. a comment with a parent_id is a reply to that
+-- comment, which is always on the same snippet.
+CREATE TABLE comments(
+    id INTEGER PRIMARY KEY NOT NULL,
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    parent_id INTEGER REFERENCES comments(id),
+    author_id INTEGER NOT NULL REFERENCES users(id),
+    body TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    -- deleted comments keep their place in the thread so that the replies to
+    -- them still make sense, but lose their body.
+    deleted_at TIMESTAMP
+);
+
+CREATE INDEX comments_snippet_id ON comments(snippet_id, created_at);
@@ -0,0 +1,11 @@
+-- snippets users have saved for later. each user bookmarks a snippet at most
+-- once, and removing a bookmark deletes its row.
+CREATE TABLE bookmarks(
+    id INTEGER PRIMARY KEY NOT NULL,
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE(user_id, snippet_id)
+);
+
+CREATE INDEX bookmarks_user_id_created_at ON bookmarks(user_id, created_at);
@@ -0,0 +1,10 @@
+-- how many times each snippet's link was followed, one row per snippet per
+-- day. nothing about who clicked is kept, not even their address.
+CREATE TABLE snippet_clicks(
+    snippet_id INTEGER NOT NULL REFERENCES snippets(id),
+    day DATE NOT NULL,
+    clicks INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY(snippet_id, day)
+);
+
+CREATE INDEX snippet_clicks_day ON snippet_clicks(day);
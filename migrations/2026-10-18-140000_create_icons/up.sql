@@ -0,0 +1,23 @@
+-- the icons a snippet can have. the first few ship with the frontend; the
+-- rest are uploaded by admins and kept on disk next to the database.
+CREATE TABLE icons(
+    id INTEGER PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    filename TEXT NOT NULL UNIQUE,
+    content_type TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+INSERT INTO icons(name, filename, content_type, created_at) VALUES
+    ('Blender3D', 'blender.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Deepnight', 'deepnight.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Ferris', 'ferris-happy.svg', 'image/svg+xml', CURRENT_TIMESTAMP),
+    ('Firefox', 'firefox.svg', 'image/svg+xml', CURRENT_TIMESTAMP),
+    ('GDC', 'gdc.svg', 'image/svg+xml', CURRENT_TIMESTAMP),
+    ('Gamasutra', 'gamasutra.ico', 'image/x-icon', CURRENT_TIMESTAMP),
+    ('Github', 'github.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Godot', 'godot.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Itch.io', 'itchio.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Safari', 'safari.png', 'image/png', CURRENT_TIMESTAMP),
+    ('Swift', 'swiftlang.svg', 'image/svg+xml', CURRENT_TIMESTAMP),
+    ('YouTube', 'youtube.svg', 'image/svg+xml', CURRENT_TIMESTAMP);
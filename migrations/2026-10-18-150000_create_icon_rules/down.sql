@@ -0,0 +1 @@
+DROP TABLE icon_rules;
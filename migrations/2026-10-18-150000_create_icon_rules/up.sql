@@ -0,0 +1,23 @@
+-- which icon links to a site get when nobody picks one. a pattern is either a
+-- host, which also covers its www. subdomain, or *. and a domain, which
+-- covers the domain and every subdomain of it.
+CREATE TABLE icon_rules(
+    id INTEGER PRIMARY KEY NOT NULL,
+    pattern TEXT NOT NULL UNIQUE,
+    icon TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+INSERT INTO icon_rules(pattern, icon, created_at) VALUES
+    ('*.blender.org', 'blender.png', CURRENT_TIMESTAMP),
+    ('*.deepnight.net', 'deepnight.png', CURRENT_TIMESTAMP),
+    ('*.gamasutra.com', 'gamasutra.ico', CURRENT_TIMESTAMP),
+    ('*.gdconf.com', 'gdc.svg', CURRENT_TIMESTAMP),
+    ('*.github.com', 'github.png', CURRENT_TIMESTAMP),
+    ('*.godotengine.org', 'godot.png', CURRENT_TIMESTAMP),
+    ('*.itch.io', 'itchio.png', CURRENT_TIMESTAMP),
+    ('*.mozilla.org', 'firefox.svg', CURRENT_TIMESTAMP),
+    ('*.rust-lang.org', 'ferris-happy.svg', CURRENT_TIMESTAMP),
+    ('*.swift.org', 'swiftlang.svg', CURRENT_TIMESTAMP),
+    ('*.youtu.be', 'youtube.svg', CURRENT_TIMESTAMP),
+    ('*.youtube.com', 'youtube.svg', CURRENT_TIMESTAMP);
@@ -0,0 +1,4 @@
+-- the names of the snippet's tags as of each revision, as a JSON array.
+-- revisions from before this was kept have no idea what their tags were, so
+-- they're left null and restoring one of them leaves the tags alone.
+ALTER TABLE snippet_revisions ADD COLUMN tags TEXT;
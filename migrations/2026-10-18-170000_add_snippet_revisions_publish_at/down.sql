@@ -0,0 +1 @@
+ALTER TABLE snippet_revisions DROP COLUMN publish_at;
@@ -0,0 +1,3 @@
+-- when the snippet was scheduled to go up as of each revision, so that
+-- rescheduling shows up in the history and is undone by a restore.
+ALTER TABLE snippet_revisions ADD COLUMN publish_at TIMESTAMP;
@@ -4,6 +4,7 @@ import {
   GetSessionOutput, GetGithubAuthorizationUrlOutput, GetGithubCallbackInput,
   GetGithubCallbackOutput, DeleteSessionOutput,
 } from './auth';
+import { GetIconsOutput } from './icons';
 import {
   CreateSnippetInput, CreateSnippetOutput, GetSnippetInput, GetSnippetOutput,
   ListSnippetInput, ListSnippetOutput, Snippet, UpdateSnippetInput,
@@ -150,6 +151,18 @@ export class HttpClient {
     return output;
   }
 
+  /**
+   * Lists every icon a snippet can have.
+   * @returns the icons, by name.
+   */
+  async getIcons(): Promise<GetIconsOutput> {
+    const response = await fetch(
+      this.baseUrl + '/icons',
+      this.defaultFetchArgs('GET', null),
+    );
+    return response.json();
+  }
+
   defaultFetchArgs<T>(method: string, body: T): RequestInit {
     let args: RequestInit = {
       method: method,
@@ -176,6 +189,9 @@ function parseSnippetDates(snippet: Snippet): Snippet {
   snippet.createdAt = new Date(snippet.createdAt);
   snippet.updatedAt = new Date(snippet.updatedAt);
   snippet.sharedOn = new Date(snippet.sharedOn);
+  if (snippet.publishAt) {
+    snippet.publishAt = new Date(snippet.publishAt);
+  }
   return snippet;
 }
 
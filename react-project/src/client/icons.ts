@@ -0,0 +1,21 @@
+/**
+ * An image a snippet can use as its icon. Icons are served from
+ * `/icons/<filename>`.
+ */
+export interface Icon {
+  id: number;
+  /**
+   * What people call the icon, such as "YouTube".
+   */
+  name: string;
+  /**
+   * What snippets put in their `icon` to use this one.
+   */
+  filename: string;
+  contentType: string;
+  createdAt: Date;
+}
+
+export interface GetIconsOutput {
+  icons: Icon[];
+}
@@ -44,14 +44,28 @@ export interface Snippet {
    */
   summary: string;
   /**
-   * A longform description of this snippet. Currently unused and most
-   * snippets don't have this filled in.
+   * A longform description of this snippet, in Markdown. Most snippets
+   * don't have this filled in.
    */
   description: string;
   /**
    * Snippets are links to further content. This is that link.
    */
   href: string;
+  /**
+   * The summary rendered from Markdown to sanitized HTML by the server.
+   * Only present on snippets from the get and list calls.
+   */
+  summaryHtml?: string;
+  /**
+   * The description rendered from Markdown to sanitized HTML by the
+   * server. Only present on snippets from the get and list calls.
+   */
+  descriptionHtml?: string;
+  /**
+   * Free-form tags on this snippet, lowercase and alphabetical.
+   */
+  tags?: Array<string>;
   /**
    * When this snippet was created.
    */
@@ -60,6 +74,30 @@ export interface Snippet {
    * The date of the last update to this snippet.
    */
   updatedAt: Date;
+  /**
+   * When this snippet goes public. Until then it is hidden from everyone
+   * but admins. Null if it went public as soon as it was shared.
+   */
+  publishAt?: Date | null;
+  /**
+   * The href with tracking parameters and the like taken out, which is
+   * what duplicate links are spotted by.
+   */
+  canonicalHref?: string;
+  /**
+   * How many users have voted this snippet up.
+   */
+  voteCount?: number;
+  /**
+   * Whether the logged in user has voted this snippet up. Absent when
+   * nobody is logged in.
+   */
+  voted?: boolean;
+  /**
+   * Whether the logged in user has bookmarked this snippet. Absent when
+   * nobody is logged in.
+   */
+  bookmarked?: boolean;
 }
 
 /**
@@ -108,6 +146,21 @@ export interface CreateSnippetInput {
    * Snippets are links to further content. This is that link.
    */
   href: string;
+  /**
+   * Keeps the snippet hidden until this time. Leave it out or null to
+   * publish right away.
+   */
+  publishAt?: Date | null;
+  /**
+   * Free-form tags on this snippet. When updating, leaving this out
+   * leaves the snippet's tags as they were.
+   */
+  tags?: Array<string>;
+  /**
+   * Creating a snippet for a link which has already been shared fails
+   * with a 409 and the existingSnippetId, unless this is set.
+   */
+  allowDuplicate?: boolean;
 }
 
 /**
@@ -155,6 +208,11 @@ export interface ListSnippetOutput {
    * The total number of pages in the snippet list.
    */
   totalPages: number;
+  /**
+   * Pass this as the cursor to pick up where this page left off. Null on
+   * the last page.
+   */
+  nextCursor: string | null;
 }
 
 /**
@@ -222,6 +280,16 @@ export interface UpdateSnippetInput {
    * Snippets are links to further content. This is that link.
    */
   href: string;
+  /**
+   * Keeps the snippet hidden until this time. Leave it out or null to
+   * publish right away.
+   */
+  publishAt?: Date | null;
+  /**
+   * Free-form tags on this snippet. When updating, leaving this out
+   * leaves the snippet's tags as they were.
+   */
+  tags?: Array<string>;
 }
 
 export interface UpdateSnippetOutput {
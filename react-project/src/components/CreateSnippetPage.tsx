@@ -5,7 +5,6 @@ import { Snippet } from '../client/snippets';
 import { useAppSelector } from '../hooks';
 import AdminOnly from './AdminOnly';
 import SnippetForm from './SnippetForm';
-import icons from '../icons';
 import { editSnippetPage } from '../namedRoutes';
 
 interface RouteParams {
@@ -25,7 +24,7 @@ export default function CreateSnippetPage() {
     taxonomy,
     hidden: true,
     title: '',
-    icon: icons['Safari'],
+    icon: 'safari.png',
     sharedBy: '',
     sharedOn: new Date(Date.now()),
     summary: '',
@@ -1,11 +1,13 @@
 import * as yup from 'yup';
 import Col from 'react-bootstrap/Col';
-import icons from '../icons';
 import { Button, Form } from 'react-bootstrap';
 import { Formik } from 'formik';
+import { HttpClient } from '../client/client';
+import { Icon } from '../client/icons';
 import { Snippet } from '../client/snippets';
-import { fromSimpleDate, toSimpleDate } from '../dateUtils';
-import { useState } from 'react';
+import { fromSimpleDate, fromSimpleDateTime, toSimpleDate, toSimpleDateTime } from '../dateUtils';
+import { useAppSelector } from '../hooks';
+import { useEffect, useMemo, useState } from 'react';
 
 export interface IEditSnippetFormProps {
   title: string,
@@ -18,10 +20,11 @@ const schema = yup.object().shape({
   sharedBy: yup.string().required(),
   href: yup.string().url().required(),
   sharedOn: yup.date().required(),
+  publishAt: yup.string(),
   hidden: yup.boolean().required(),
   summary: yup.string().required(),
   description: yup.string(),
-  icon: yup.string().required().oneOf(Object.values(icons)),
+  icon: yup.string().required(),
 });
 
 export interface ISnippetFormValues {
@@ -29,6 +32,7 @@ export interface ISnippetFormValues {
   sharedBy: string,
   href: string,
   sharedOn: Date,
+  publishAt: Date | null,
   hidden: boolean,
   summary: string,
   description: string,
@@ -37,11 +41,28 @@ export interface ISnippetFormValues {
 
 export default function SnippetForm(props: IEditSnippetFormProps) {
   let snippet = useState(props.snippet)[0];
+  const clientProps = useAppSelector(state => state.clientProps);
+  const client = useMemo(() => new HttpClient(clientProps), [clientProps]);
+  const [icons, setIcons] = useState<Icon[]>([]);
+
+  useEffect(() => {
+    client.getIcons().then((getIconsOutput) => {
+      setIcons(getIconsOutput.icons);
+    });
+  }, [client]);
 
   return <Formik
-    initialValues={{ ...snippet, sharedOn: toSimpleDate(snippet.sharedOn) }}
+    initialValues={{
+      ...snippet,
+      sharedOn: toSimpleDate(snippet.sharedOn),
+      publishAt: toSimpleDateTime(snippet.publishAt),
+    }}
     validationSchema={schema}
-    onSubmit={(values) => props.onSubmit({ ...values, sharedOn: fromSimpleDate(values.sharedOn), })}>
+    onSubmit={(values) => props.onSubmit({
+      ...values,
+      sharedOn: fromSimpleDate(values.sharedOn),
+      publishAt: fromSimpleDateTime(values.publishAt),
+    })}>
     {({
       handleSubmit,
       handleChange,
@@ -136,20 +157,17 @@ export default function SnippetForm(props: IEditSnippetFormProps) {
               onChange={handleChange}
               onBlur={handleBlur}
               isInvalid={touched.icon && !!errors.icon}>
-              {Object.entries(icons).map(([iconName, iconFile]) => {
+              {icons.map((icon) => {
                 return <option
-                  key={iconFile}
-                  value={iconFile}>
-                  {iconName}
+                  key={icon.filename}
+                  value={icon.filename}>
+                  {icon.name}
                 </option>;
               })}
             </Form.Control>
             <Form.Text>
-              Pick the icon used for this link. Adding icons requires a
-              code-change to the website, see <a
-                href="https://github.com/idevgames/idevgames.com/blob/mainline/react-project/src/icons.ts">
-                <code>icons.ts</code>
-              </a>.
+              Pick the icon used for this link. Admins can add icons by
+              uploading them to <code>POST /api/icons</code>.
             </Form.Text>
           </Form.Group>
           <Form.Group as={Col} controlId="summary">
@@ -188,6 +206,25 @@ export default function SnippetForm(props: IEditSnippetFormProps) {
             </Form.Control.Feedback>
           </Form.Group>
         </Form.Row>
+        <Form.Row>
+          <Form.Group as={Col} controlId="publishAt">
+            <Form.Label>Publish at</Form.Label>
+            <Form.Control
+              type="datetime-local"
+              name="publishAt"
+              value={values.publishAt}
+              onChange={handleChange}
+              onBlur={handleBlur}
+              isInvalid={touched.publishAt && !!errors.publishAt} />
+            <Form.Text>
+              Keep this snippet hidden until this time, in your local time.
+              Leave it blank to publish right away.
+            </Form.Text>
+            <Form.Control.Feedback type="invalid">
+              {errors.publishAt}
+            </Form.Control.Feedback>
+          </Form.Group>
+        </Form.Row>
         <Form.Row>
           <Form.Group as={Col} controlId="hidden">
             <Form.Check
@@ -5,3 +5,25 @@ export function toSimpleDate(date: Date): string {
 export function fromSimpleDate(str: string): Date {
   return new Date(Date.parse(str));
 }
+
+function pad(n: number): string {
+  return n.toLocaleString('en', { minimumIntegerDigits: 2 });
+}
+
+/**
+ * Formats a date and time the way a datetime-local input wants it, in the
+ * browser's local time. Empty when there's no date at all.
+ */
+export function toSimpleDateTime(date?: Date | null): string {
+  if (!date) {
+    return '';
+  }
+  return `${toSimpleDate(date)}T${pad(date.getHours())}:${pad(date.getMinutes())}`;
+}
+
+/**
+ * The reverse of toSimpleDateTime: null for an empty input.
+ */
+export function fromSimpleDateTime(str: string): Date | null {
+  return str ? new Date(Date.parse(str)) : null;
+}